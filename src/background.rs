@@ -0,0 +1,326 @@
+//! Writing log statements on a background thread.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+
+use log;
+
+use format;
+use Output;
+
+/// What to do with a log statement when the queue of the background thread is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueueFull {
+    /// The log statement waits for room in the queue, which slows down the program to the speed
+    /// of the outputs but never loses a log statement.
+    Block,
+    /// The log statement is dropped.
+    DropNewest,
+    /// The oldest log statement in the queue is dropped to make room.
+    DropOldest,
+}
+
+/// A formatted log statement waiting to be written to its output, with the parts of its record
+/// that the outputs use.
+struct Entry {
+    output: Output,
+    level: log::Level,
+    target: String,
+    module_path: Option<String>,
+    file: Option<String>,
+    line: Option<u32>,
+    message: String,
+    key_values: Vec<(String, String)>,
+    text: String,
+}
+
+impl Entry {
+    fn new(output: &Output, record: &log::Record, text: &str) -> Entry {
+        Entry {
+            output: output.clone(),
+            level: record.level(),
+            target: record.target().to_string(),
+            module_path: record.module_path().map(String::from),
+            file: record.file().map(String::from),
+            line: record.line(),
+            message: record.args().to_string(),
+            key_values: format::key_values(record)
+                .into_iter()
+                .map(|kv| (kv.key, kv.value))
+                .collect(),
+            text: text.to_string(),
+        }
+    }
+}
+
+struct State {
+    entries: VecDeque<Entry>,
+    dropped: u64,
+    busy: bool,
+    closed: bool,
+}
+
+/// A bounded queue of log statements shared by the logger and the background thread.
+struct Queue {
+    capacity: usize,
+    policy: QueueFull,
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+impl Queue {
+    fn new(capacity: usize, policy: QueueFull) -> Queue {
+        Queue {
+            capacity: capacity.max(1),
+            policy,
+            state: Mutex::new(State {
+                entries: VecDeque::new(),
+                dropped: 0,
+                busy: false,
+                closed: false,
+            }),
+            changed: Condvar::new(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn wait<'a>(&self, state: MutexGuard<'a, State>) -> MutexGuard<'a, State> {
+        self.changed.wait(state).unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Adds a log statement to the queue based on the policy. Returns `false` if the queue is
+    /// closed.
+    fn push(&self, entry: Entry) -> bool {
+        let mut state = self.lock();
+        if self.policy == QueueFull::Block {
+            while !state.closed && state.entries.len() >= self.capacity {
+                state = self.wait(state);
+            }
+        }
+        if state.closed {
+            return false;
+        }
+        if state.entries.len() >= self.capacity {
+            state.dropped += 1;
+            match self.policy {
+                QueueFull::DropNewest => return true,
+                _ => {
+                    state.entries.pop_front();
+                },
+            }
+        }
+        state.entries.push_back(entry);
+        self.changed.notify_all();
+        true
+    }
+
+    /// Takes the next log statement and the number of dropped log statements, or `None` once the
+    /// queue is closed and empty.
+    fn pop(&self) -> Option<(Entry, u64)> {
+        let mut state = self.lock();
+        state.busy = false;
+        self.changed.notify_all();
+        loop {
+            if let Some(entry) = state.entries.pop_front() {
+                state.busy = true;
+                let dropped = state.dropped;
+                state.dropped = 0;
+                self.changed.notify_all();
+                return Some((entry, dropped));
+            }
+            if state.closed {
+                return None;
+            }
+            state = self.wait(state);
+        }
+    }
+
+    /// Waits until the queued log statements are written, or the queue is closed.
+    fn wait_idle(&self) {
+        let mut state = self.lock();
+        while !state.closed && (state.busy || !state.entries.is_empty()) {
+            state = self.wait(state);
+        }
+    }
+
+    fn close(&self) {
+        self.lock().closed = true;
+        self.changed.notify_all();
+    }
+}
+
+/// Closes the queue when the background thread exits, even if it panics, so the logger goes
+/// back to writing log statements itself.
+struct CloseOnExit(Arc<Queue>);
+
+impl Drop for CloseOnExit {
+    fn drop(&mut self) {
+        let mut state = self.0.lock();
+        state.closed = true;
+        state.busy = false;
+        self.0.changed.notify_all();
+    }
+}
+
+/// A background thread that writes the log statements from a queue.
+///
+/// Clones share the thread. Two background writers are equal if they have the same capacity and
+/// policy.
+#[derive(Clone)]
+pub struct Background {
+    queue: Arc<Queue>,
+    thread: Arc<Mutex<Option<JoinHandle<()>>>>,
+}
+
+impl Background {
+    /// Starts a background thread with a queue of a capacity.
+    pub fn start(capacity: usize, policy: QueueFull) -> io::Result<Background> {
+        let queue = Arc::new(Queue::new(capacity, policy));
+        let thread_queue = queue.clone();
+        let thread = thread::Builder::new()
+            .name(String::from("loggerv"))
+            .spawn(move || {
+                let _close = CloseOnExit(thread_queue.clone());
+                while let Some((entry, dropped)) = thread_queue.pop() {
+                    report_dropped(dropped);
+                    write_entry(&entry);
+                }
+                report_dropped(thread_queue.lock().dropped);
+            })?;
+        Ok(Background {
+            queue,
+            thread: Arc::new(Mutex::new(Some(thread))),
+        })
+    }
+
+    /// Gets the capacity of the queue and the policy when it is full.
+    pub fn settings(&self) -> (usize, QueueFull) {
+        (self.queue.capacity, self.queue.policy)
+    }
+
+    /// Queues a log statement with its text for an output. Returns `false` if the background
+    /// thread has been stopped, so the caller has to write the log statement.
+    pub fn send(&self, output: &Output, record: &log::Record, text: &str) -> bool {
+        self.queue.push(Entry::new(output, record, text))
+    }
+
+    /// Waits until the queued log statements are written.
+    pub fn wait_idle(&self) {
+        self.queue.wait_idle()
+    }
+
+    /// Stops the background thread after it writes the queued log statements.
+    pub fn stop(&self) {
+        self.queue.close();
+        let thread = self.thread.lock().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some(t) = thread {
+            // A panic of the background thread has already been reported by the panic hook.
+            let _ = t.join();
+        }
+    }
+}
+
+impl fmt::Debug for Background {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Background")
+            .field("capacity", &self.queue.capacity)
+            .field("policy", &self.queue.policy)
+            .finish()
+    }
+}
+
+impl PartialEq for Background {
+    fn eq(&self, other: &Background) -> bool {
+        self.queue.capacity == other.queue.capacity && self.queue.policy == other.queue.policy
+    }
+}
+
+fn report_dropped(dropped: u64) {
+    if dropped > 0 {
+        let _ = writeln!(
+            io::stderr(),
+            "loggerv: dropped {} log statements because the queue was full",
+            dropped
+        );
+    }
+}
+
+fn write_entry(e: &Entry) {
+    let kvs: Vec<(&str, &str)> = e.key_values.iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect();
+    ::write_output(&e.output, &log::Record::builder()
+        .level(e.level)
+        .target(&e.target)
+        .module_path(e.module_path.as_deref())
+        .file(e.file.as_deref())
+        .line(e.line)
+        .key_values(&kvs)
+        .args(format_args!("{}", e.message))
+        .build(), &e.text);
+}
+
+#[cfg(test)]
+mod tests {
+    use testing;
+    use super::*;
+
+    fn entry(capture: &testing::Capture, message: &str) -> Entry {
+        Entry::new(&capture.output(), &log::Record::builder()
+            .args(format_args!("{}", message))
+            .build(), message)
+    }
+
+    fn messages(queue: &Queue) -> Vec<String> {
+        queue.lock().entries.iter().map(|e| e.message.clone()).collect()
+    }
+
+    #[test]
+    fn push_works() {
+        let capture = testing::capture();
+        let queue = Queue::new(2, QueueFull::DropNewest);
+        for m in &["one", "two", "three"] {
+            assert!(queue.push(entry(&capture, m)));
+        }
+        assert_eq!(messages(&queue), vec!["one", "two"]);
+        assert_eq!(queue.lock().dropped, 1);
+        let queue = Queue::new(2, QueueFull::DropOldest);
+        for m in &["one", "two", "three"] {
+            assert!(queue.push(entry(&capture, m)));
+        }
+        assert_eq!(messages(&queue), vec!["two", "three"]);
+        let (next, dropped) = queue.pop().unwrap();
+        assert_eq!((next.message.as_str(), dropped), ("two", 1));
+        queue.close();
+        assert!(!queue.push(entry(&capture, "four")));
+        assert!(queue.pop().is_some());
+        assert!(queue.pop().is_none());
+    }
+
+    #[test]
+    fn background_works() {
+        let capture = testing::capture();
+        let background = Background::start(1, QueueFull::Block).unwrap();
+        let record = log::Record::builder()
+            .level(log::Level::Warn)
+            .key_values(&[("attempt", 2)])
+            .args(format_args!("retrying"))
+            .build();
+        for _ in 0..3 {
+            assert!(background.send(&capture.output(), &record, "app: retrying"));
+        }
+        background.wait_idle();
+        assert_eq!(capture.lines(), vec!["app: retrying"; 3]);
+        assert_eq!(capture.records()[0].key_values, vec![
+            (String::from("attempt"), String::from("2"))
+        ]);
+        background.stop();
+        assert!(!background.send(&capture.output(), &record, "app: retrying"));
+    }
+}
@@ -0,0 +1,159 @@
+//! Filters on the text of log statements with regular expressions.
+
+use std::fmt;
+
+use log;
+use regex::{self, Regex};
+
+/// A filter that includes or excludes log statements based on their message.
+///
+/// The message is the text of the log statement after formatting its arguments, without the "tag"
+/// and the key-value pairs. A filter can be limited to the log statements with a target, which
+/// is the module path unless the log statement sets another target, that also matches a regular
+/// expression. Regular expressions match anywhere in the text unless they are anchored with `^`
+/// or `$`.
+///
+/// Two filters are equal if they have the same regular expressions.
+///
+/// # Example
+///
+/// ```rust
+/// #[macro_use] extern crate log;
+/// extern crate loggerv;
+///
+/// use loggerv::MessageFilter;
+///
+/// fn main() {
+///     loggerv::Logger::new()
+///         .message_filter(MessageFilter::exclude("connection reset by peer")
+///             .unwrap()
+///             .target("^hyper(::|$)")
+///             .unwrap())
+///         .init()
+///         .unwrap();
+///
+///     warn!("This is printed, since only messages from hyper are filtered");
+/// }
+/// ```
+#[derive(Clone)]
+pub struct MessageFilter {
+    message: Regex,
+    target: Option<Regex>,
+    exclude: bool,
+}
+
+impl MessageFilter {
+    /// Creates a filter that only logs the log statements with a message that matches a regular
+    /// expression.
+    ///
+    /// If there is more than one of these filters for a log statement, then a message that
+    /// matches any of them is logged.
+    pub fn include(pattern: &str) -> Result<MessageFilter, regex::Error> {
+        MessageFilter::new(pattern, false)
+    }
+
+    /// Creates a filter that drops the log statements with a message that matches a regular
+    /// expression.
+    ///
+    /// Exclusions win over the filters from the `include` function.
+    pub fn exclude(pattern: &str) -> Result<MessageFilter, regex::Error> {
+        MessageFilter::new(pattern, true)
+    }
+
+    fn new(pattern: &str, exclude: bool) -> Result<MessageFilter, regex::Error> {
+        Ok(MessageFilter {
+            message: Regex::new(pattern)?,
+            target: None,
+            exclude,
+        })
+    }
+
+    /// Limits the filter to the log statements with a target that matches a regular expression.
+    pub fn target(mut self, pattern: &str) -> Result<Self, regex::Error> {
+        self.target = Some(Regex::new(pattern)?);
+        Ok(self)
+    }
+
+    fn applies_to(&self, record: &log::Record) -> bool {
+        self.target.as_ref().map_or(true, |t| t.is_match(record.target()))
+    }
+}
+
+impl fmt::Debug for MessageFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MessageFilter")
+            .field("message", &self.message.as_str())
+            .field("target", &self.target.as_ref().map(Regex::as_str))
+            .field("exclude", &self.exclude)
+            .finish()
+    }
+}
+
+impl PartialEq for MessageFilter {
+    fn eq(&self, other: &MessageFilter) -> bool {
+        self.message.as_str() == other.message.as_str()
+            && self.target.as_ref().map(Regex::as_str) == other.target.as_ref().map(Regex::as_str)
+            && self.exclude == other.exclude
+    }
+}
+
+impl Eq for MessageFilter {}
+
+/// Checks if message filters allow a log statement.
+///
+/// The log statement is dropped if an exclusion for it matches its message, or if there are
+/// inclusions for it and none of them matches its message.
+pub fn allows(filters: &[MessageFilter], record: &log::Record) -> bool {
+    let mut applicable = filters.iter().filter(|f| f.applies_to(record)).peekable();
+    if applicable.peek().is_none() {
+        return true;
+    }
+    let message = record.args().to_string();
+    let mut included = None;
+    for filter in applicable {
+        let matched = filter.message.is_match(&message);
+        if filter.exclude {
+            if matched {
+                return false;
+            }
+        } else {
+            included = Some(included.unwrap_or(false) || matched);
+        }
+    }
+    included.unwrap_or(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allows_message(filters: &[MessageFilter], target: &str, message: &str) -> bool {
+        allows(filters, &log::Record::builder()
+            .target(target)
+            .args(format_args!("{}", message))
+            .build())
+    }
+
+    #[test]
+    fn allows_works() {
+        assert!(allows_message(&[], "app", "anything"));
+        let filters = [
+            MessageFilter::exclude("^connection reset").unwrap().target("^hyper").unwrap(),
+            MessageFilter::include("user").unwrap().target("^app::auth$").unwrap(),
+            MessageFilter::include("login").unwrap().target("^app::auth$").unwrap(),
+        ];
+        assert!(!allows_message(&filters, "hyper::proto", "connection reset by peer"));
+        assert!(allows_message(&filters, "hyper::proto", "connection refused"));
+        assert!(allows_message(&filters, "app", "connection reset by peer"));
+        assert!(allows_message(&filters, "app::auth", "user 42 logged in"));
+        assert!(allows_message(&filters, "app::auth", "login failed"));
+        assert!(!allows_message(&filters, "app::auth", "session expired"));
+    }
+
+    #[test]
+    fn equality_works() {
+        assert_eq!(MessageFilter::include("a+").unwrap(), MessageFilter::include("a+").unwrap());
+        assert_ne!(MessageFilter::include("a+").unwrap(), MessageFilter::exclude("a+").unwrap());
+        assert!(MessageFilter::include("(").is_err());
+    }
+}
@@ -0,0 +1,306 @@
+//! Machine-readable formats for a log statement.
+
+use std::fmt::Write;
+use std::thread;
+
+use log;
+use log::kv::{self, VisitSource};
+
+use {Logger, Timestamp};
+
+/// Renders a log statement as a JSON object on a single line.
+pub fn json(logger: &Logger, record: &log::Record) -> String {
+    let mut line = String::from("{");
+    json_field(&mut line, "time", &machine_timestamp(logger));
+    line.push(',');
+    json_field(&mut line, "level", &record.level().to_string());
+    line.push(',');
+    json_field(&mut line, "target", record.target());
+    line.push_str(",\"module_path\":");
+    json_option(&mut line, record.module_path());
+    line.push_str(",\"file\":");
+    json_option(&mut line, record.file());
+    line.push_str(",\"line\":");
+    match record.line() {
+        Some(l) => write!(line, "{}", l).expect("Writing to a string"),
+        None => line.push_str("null"),
+    }
+    line.push(',');
+    json_field(&mut line, "thread", &thread_name());
+    line.push(',');
+    json_field(&mut line, "msg", &record.args().to_string());
+    let kvs = key_values(record);
+    if !kvs.is_empty() {
+        line.push_str(",\"kv\":{");
+        for (i, kv) in kvs.iter().enumerate() {
+            if i > 0 {
+                line.push(',');
+            }
+            json_string(&mut line, &kv.key);
+            line.push(':');
+            if kv.literal {
+                line.push_str(&kv.value);
+            } else {
+                json_string(&mut line, &kv.value);
+            }
+        }
+        line.push('}');
+    }
+    line.push('}');
+    line
+}
+
+/// The keys of the logfmt format that the key-value pairs must not repeat.
+const LOGFMT_KEYS: &[&str] = &[
+    "time",
+    "level",
+    "target",
+    "module_path",
+    "file",
+    "line",
+    "thread",
+    "msg",
+];
+
+/// Renders a log statement as space-separated `key=value` pairs in the logfmt format.
+pub fn logfmt(logger: &Logger, record: &log::Record) -> String {
+    let mut line = String::new();
+    logfmt_field(&mut line, "time", &machine_timestamp(logger));
+    logfmt_field(&mut line, "level", &record.level().to_string().to_lowercase());
+    logfmt_field(&mut line, "target", record.target());
+    if let Some(m) = record.module_path() {
+        logfmt_field(&mut line, "module_path", m);
+    }
+    if let Some(f) = record.file() {
+        logfmt_field(&mut line, "file", f);
+    }
+    if let Some(l) = record.line() {
+        logfmt_field(&mut line, "line", &l.to_string());
+    }
+    logfmt_field(&mut line, "thread", &thread_name());
+    logfmt_field(&mut line, "msg", &record.args().to_string());
+    for kv in key_values(record) {
+        let key = logfmt_key(&kv.key);
+        if LOGFMT_KEYS.contains(&key.as_str()) {
+            logfmt_field(&mut line, &format!("kv_{}", key), &kv.value);
+        } else {
+            logfmt_field(&mut line, &key, &kv.value);
+        }
+    }
+    line
+}
+
+/// A key-value pair of a log statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyValue {
+    pub key: String,
+    pub value: String,
+    /// The value is a boolean or a number, which machine-readable formats do not need to quote.
+    pub literal: bool,
+}
+
+struct Collect(Vec<KeyValue>);
+
+impl<'kvs> VisitSource<'kvs> for Collect {
+    fn visit_pair(&mut self, key: kv::Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+        let literal = value.to_bool().is_some()
+            || value.to_i64().is_some()
+            || value.to_u64().is_some()
+            || value.to_f64().is_some_and(f64::is_finite);
+        self.0.push(KeyValue {
+            key: key.to_string(),
+            value: value.to_string(),
+            literal,
+        });
+        Ok(())
+    }
+}
+
+/// Gets the key-value pairs of a log statement.
+pub fn key_values(record: &log::Record) -> Vec<KeyValue> {
+    let mut collect = Collect(Vec::new());
+    // Collecting the key-value pairs never fails, and a failing source only loses its pairs.
+    let _ = record.key_values().visit(&mut collect);
+    collect.0
+}
+
+/// Gets the name of the current thread, or its ID if it is unnamed.
+pub fn thread_name() -> String {
+    let current = thread::current();
+    match current.name() {
+        Some(name) => name.to_string(),
+        None => {
+            // The `ThreadId` type only exposes its number through its `Debug` implementation,
+            // which is `ThreadId(N)`.
+            format!("{:?}", current.id()).chars().filter(char::is_ascii_digit).collect()
+        },
+    }
+}
+
+/// Gets the timestamp for a machine-readable format, which is always a wall-clock time.
+fn machine_timestamp(logger: &Logger) -> String {
+    match logger.timestamp {
+        Timestamp::Rfc3339Local => logger.create_timestamp(Timestamp::Rfc3339Local),
+        _ => logger.create_timestamp(Timestamp::Rfc3339Utc),
+    }
+}
+
+fn logfmt_field(line: &mut String, key: &str, value: &str) {
+    if !line.is_empty() {
+        line.push(' ');
+    }
+    line.push_str(key);
+    line.push('=');
+    logfmt_value(line, value);
+}
+
+/// Converts a key of a key-value pair to a logfmt key, which has no spaces, equal signs, quotes, or
+/// control characters.
+pub fn logfmt_key(key: &str) -> String {
+    let key: String = key.chars()
+        .map(|c| if c == ' ' || c == '=' || c == '"' || c.is_control() { '_' } else { c })
+        .collect();
+    if key.is_empty() {
+        String::from("key")
+    } else {
+        key
+    }
+}
+
+pub fn logfmt_value(line: &mut String, value: &str) {
+    let quote = value.is_empty()
+        || value.chars().any(|c| c == ' ' || c == '=' || c == '"' || c == '\\' || c.is_control());
+    if !quote {
+        line.push_str(value);
+        return;
+    }
+    line.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => line.push_str("\\\""),
+            '\\' => line.push_str("\\\\"),
+            '\n' => line.push_str("\\n"),
+            '\r' => line.push_str("\\r"),
+            '\t' => line.push_str("\\t"),
+            c if c.is_control() => {
+                write!(line, "\\u{:04x}", c as u32).expect("Writing to a string")
+            },
+            c => line.push(c),
+        }
+    }
+    line.push('"');
+}
+
+fn json_field(line: &mut String, key: &str, value: &str) {
+    json_string(line, key);
+    line.push(':');
+    json_string(line, value);
+}
+
+fn json_option(line: &mut String, value: Option<&str>) {
+    match value {
+        Some(v) => json_string(line, v),
+        None => line.push_str("null"),
+    }
+}
+
+fn json_string(line: &mut String, value: &str) {
+    line.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => line.push_str("\\\""),
+            '\\' => line.push_str("\\\\"),
+            '\n' => line.push_str("\\n"),
+            '\r' => line.push_str("\\r"),
+            '\t' => line.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                write!(line, "\\u{:04x}", c as u32).expect("Writing to a string")
+            },
+            c => line.push(c),
+        }
+    }
+    line.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_string_works() {
+        let mut line = String::new();
+        json_string(&mut line, "a \"quoted\"\\path\nnext\u{1}");
+        assert_eq!(line, r#""a \"quoted\"\\path\nnext\u0001""#);
+    }
+
+    #[test]
+    fn logfmt_value_works() {
+        let mut line = String::new();
+        logfmt_value(&mut line, "plain");
+        line.push(' ');
+        logfmt_value(&mut line, "");
+        line.push(' ');
+        logfmt_value(&mut line, "a \"quoted\" message\nnext=1");
+        assert_eq!(line, r#"plain "" "a \"quoted\" message\nnext=1""#);
+    }
+
+    #[test]
+    fn logfmt_works() {
+        let logger = Logger::new();
+        let record = log::Record::builder()
+            .level(log::Level::Warn)
+            .target("mycrate")
+            .module_path(Some("mycrate"))
+            .args(format_args!("retrying in 5s"))
+            .build();
+        let line = logfmt(&logger, &record);
+        assert!(line.starts_with("time="));
+        assert!(line.ends_with(&format!(
+            r#" level=warn target=mycrate module_path=mycrate thread={} msg="retrying in 5s""#,
+            thread_name()
+        )));
+    }
+
+    #[test]
+    fn key_values_work() {
+        let kvs: &[(&str, &dyn kv::ToValue)] = &[("user", &42), ("name", &"ann lee"), ("ok", &true)];
+        let record = log::Record::builder()
+            .target("app")
+            .args(format_args!("login"))
+            .key_values(&kvs)
+            .build();
+        let logger = Logger::new();
+        assert!(json(&logger, &record)
+            .ends_with(r#""msg":"login","kv":{"user":42,"name":"ann lee","ok":true}}"#));
+        assert!(logfmt(&logger, &record).ends_with(r#" msg=login user=42 name="ann lee" ok=true"#));
+    }
+
+    #[test]
+    fn logfmt_key_values_work() {
+        let kvs: &[(&str, &dyn kv::ToValue)] = &[("my key", &1), ("msg", &"dup"), ("", &2)];
+        let record = log::Record::builder()
+            .target("app")
+            .args(format_args!("x"))
+            .key_values(&kvs)
+            .build();
+        assert!(logfmt(&Logger::new(), &record).ends_with(" msg=x my_key=1 kv_msg=dup key=2"));
+    }
+
+    #[test]
+    fn json_works() {
+        let logger = Logger::new();
+        let record = log::Record::builder()
+            .level(log::Level::Info)
+            .target("mycrate::db")
+            .module_path(Some("mycrate::db"))
+            .line(Some(7))
+            .args(format_args!("connected"))
+            .build();
+        let line = json(&logger, &record);
+        assert!(line.starts_with(r#"{"time":""#));
+        assert!(line.ends_with(&format!(
+            r#"","level":"INFO","target":"mycrate::db","module_path":"mycrate::db","file":null,"line":7,"thread":"{}","msg":"connected"}}"#,
+            thread_name()
+        )));
+    }
+}
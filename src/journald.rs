@@ -0,0 +1,351 @@
+//! An output to the systemd journal over its native protocol.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Write};
+use std::mem;
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::os::unix::net::UnixDatagram;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use libc;
+use log;
+
+use format::key_values;
+use syslog::severity;
+
+/// The default path of the socket of the systemd journal.
+pub const DEFAULT_JOURNALD_PATH: &str = "/run/systemd/journal/socket";
+
+/// An output to the systemd journal.
+///
+/// Each log statement is sent as a journal entry with the `MESSAGE`, `PRIORITY`, `CODE_FILE`,
+/// `CODE_LINE`, `CODE_MODULE`, and `SYSLOG_IDENTIFIER` fields, so `journalctl` can filter by
+/// module path and source location. The `MESSAGE` field is the message of the log statement
+/// without the "tag", since the journal keeps the rest in its own fields. The key-value pairs of
+/// the log statement are added as fields with their keys in uppercase, such as `USER_ID` for
+/// `user_id`, and with a `KV_` prefix if they would replace one of the other fields, such as
+/// `KV_MESSAGE` for `message`. Entries that are too big for a datagram are sent through a sealed
+/// memory file instead.
+///
+/// The socket is connected again if an entry cannot be sent, such as after the journal was
+/// restarted. If the entry still cannot be sent, the log statement is written to `stderr`
+/// instead, and the first failure is reported there.
+///
+/// Clones of the output share the socket. Two outputs are equal if they share the socket.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// #[macro_use] extern crate log;
+/// extern crate loggerv;
+///
+/// use log::Level;
+/// use loggerv::{Journald, Output};
+///
+/// fn main() {
+///     let journald = Output::Journald(Journald::new().unwrap());
+///     loggerv::Logger::new()
+///         .output(&Level::Error, journald.clone())
+///         .output(&Level::Warn, journald)
+///         .init()
+///         .unwrap();
+///
+///     error!("This is sent to the journal");
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Journald {
+    path: PathBuf,
+    socket: Arc<UnixDatagram>,
+    syslog_identifier: Option<String>,
+    failed: Arc<AtomicBool>,
+}
+
+impl Journald {
+    /// Creates an output to the systemd journal.
+    pub fn new() -> io::Result<Journald> {
+        Journald::with_path(DEFAULT_JOURNALD_PATH)
+    }
+
+    /// Creates an output to a journal socket at a path.
+    pub fn with_path<P: AsRef<Path>>(path: P) -> io::Result<Journald> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(&path)?;
+        Ok(Journald {
+            path: path.as_ref().to_path_buf(),
+            socket: Arc::new(socket),
+            syslog_identifier: None,
+            failed: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// Sets the `SYSLOG_IDENTIFIER` field. The default is to let the journal use the name of the
+    /// executable.
+    pub fn syslog_identifier(mut self, i: impl Into<String>) -> Self {
+        self.syslog_identifier = Some(i.into());
+        self
+    }
+
+    /// Sends a log statement to the journal.
+    pub(crate) fn send(&self, record: &log::Record) -> io::Result<()> {
+        let mut entry = Vec::new();
+        add_field(&mut entry, "PRIORITY", &severity(record.level()).to_string());
+        add_field(&mut entry, "MESSAGE", &record.args().to_string());
+        if let Some(f) = record.file() {
+            add_field(&mut entry, "CODE_FILE", f);
+        }
+        if let Some(l) = record.line() {
+            add_field(&mut entry, "CODE_LINE", &l.to_string());
+        }
+        if let Some(m) = record.module_path() {
+            add_field(&mut entry, "CODE_MODULE", m);
+        }
+        if let Some(ref i) = self.syslog_identifier {
+            add_field(&mut entry, "SYSLOG_IDENTIFIER", i);
+        }
+        for kv in key_values(record) {
+            add_field(&mut entry, &field_name(&kv.key), &kv.value);
+        }
+        // The connection is lost when the journal is restarted, so reconnect once.
+        self.send_entry(&entry).or_else(|_| {
+            self.socket.connect(&self.path)?;
+            self.send_entry(&entry)
+        })
+    }
+
+    fn send_entry(&self, entry: &[u8]) -> io::Result<()> {
+        match self.socket.send(entry) {
+            Ok(_) => Ok(()),
+            Err(ref e) if e.raw_os_error() == Some(libc::EMSGSIZE)
+                || e.raw_os_error() == Some(libc::ENOBUFS) => self.send_memfd(entry),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Checks if a failure to send is the first one, which is the only one that is reported.
+    pub(crate) fn first_failure(&self) -> bool {
+        !self.failed.swap(true, Ordering::Relaxed)
+    }
+
+    /// Sends an entry that is too big for a datagram as a sealed memory file.
+    fn send_memfd(&self, entry: &[u8]) -> io::Result<()> {
+        let fd = unsafe {
+            libc::memfd_create(
+                b"loggerv\0".as_ptr() as *const libc::c_char,
+                libc::MFD_ALLOW_SEALING | libc::MFD_CLOEXEC,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut file = unsafe { File::from_raw_fd(fd) };
+        file.write_all(entry)?;
+        let seals = libc::F_SEAL_SHRINK | libc::F_SEAL_GROW | libc::F_SEAL_WRITE | libc::F_SEAL_SEAL;
+        if unsafe { libc::fcntl(fd, libc::F_ADD_SEALS, seals) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        send_fd(self.socket.as_raw_fd(), fd)
+    }
+}
+
+impl fmt::Debug for Journald {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Journald")
+            .field("syslog_identifier", &self.syslog_identifier)
+            .finish()
+    }
+}
+
+impl PartialEq for Journald {
+    fn eq(&self, other: &Journald) -> bool {
+        self.syslog_identifier == other.syslog_identifier
+            && Arc::ptr_eq(&self.socket, &other.socket)
+    }
+}
+
+impl Eq for Journald {}
+
+/// Adds a field to an entry, using the binary encoding with an explicit length if the value
+/// contains a newline.
+fn add_field(entry: &mut Vec<u8>, key: &str, value: &str) {
+    entry.extend_from_slice(key.as_bytes());
+    if value.contains('\n') {
+        entry.push(b'\n');
+        entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    } else {
+        entry.push(b'=');
+    }
+    entry.extend_from_slice(value.as_bytes());
+    entry.push(b'\n');
+}
+
+/// The fields of an entry that the key-value pairs must not replace.
+const RESERVED_FIELDS: &[&str] = &[
+    "MESSAGE",
+    "PRIORITY",
+    "CODE_FILE",
+    "CODE_LINE",
+    "CODE_MODULE",
+    "SYSLOG_IDENTIFIER",
+];
+
+/// Converts a key to a journal field name, which has only uppercase letters, digits, and
+/// underscores, and does not start with an underscore or a digit. Names of the fields that are
+/// sent for every entry get a `KV_` prefix.
+fn field_name(key: &str) -> String {
+    let name: String = key.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect();
+    let name = name.trim_start_matches(|c: char| c == '_' || c.is_ascii_digit());
+    if name.is_empty() {
+        String::from("KEY")
+    } else if RESERVED_FIELDS.contains(&name) {
+        format!("KV_{}", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// Sends a file descriptor without any data over a connected unix socket.
+fn send_fd(socket: RawFd, fd: RawFd) -> io::Result<()> {
+    unsafe {
+        let space = libc::CMSG_SPACE(mem::size_of::<libc::c_int>() as u32) as usize;
+        // A buffer of `u64` is aligned for the control message header.
+        let mut control = vec![0u64; space.div_ceil(mem::size_of::<u64>())];
+        let mut msg: libc::msghdr = mem::zeroed();
+        msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+        msg.msg_controllen = space as _;
+        let cmsg = libc::CMSG_FIRSTHDR(&msg);
+        (*cmsg).cmsg_level = libc::SOL_SOCKET;
+        (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+        (*cmsg).cmsg_len = libc::CMSG_LEN(mem::size_of::<libc::c_int>() as u32) as _;
+        ptr::write_unaligned(libc::CMSG_DATA(cmsg) as *mut libc::c_int, fd);
+        if libc::sendmsg(socket, &msg, 0) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::io::{Read, Seek, SeekFrom};
+    use std::process;
+    use super::*;
+
+    fn bind(name: &str) -> (UnixDatagram, Journald, std::path::PathBuf) {
+        let path = env::temp_dir().join(format!("loggerv-journald-{}-{}.sock", name, process::id()));
+        let _ = fs::remove_file(&path);
+        let server = UnixDatagram::bind(&path).unwrap();
+        let journald = Journald::with_path(&path).unwrap().syslog_identifier("app");
+        (server, journald, path)
+    }
+
+    /// Receives a file descriptor sent with `send_fd`.
+    fn recv_fd(socket: &UnixDatagram) -> File {
+        unsafe {
+            let space = libc::CMSG_SPACE(mem::size_of::<libc::c_int>() as u32) as usize;
+            let mut control = vec![0u64; space.div_ceil(mem::size_of::<u64>())];
+            let mut msg: libc::msghdr = mem::zeroed();
+            msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+            msg.msg_controllen = space as _;
+            assert!(libc::recvmsg(socket.as_raw_fd(), &mut msg, 0) >= 0);
+            let cmsg = libc::CMSG_FIRSTHDR(&msg);
+            assert!(!cmsg.is_null());
+            File::from_raw_fd(ptr::read_unaligned(libc::CMSG_DATA(cmsg) as *const libc::c_int))
+        }
+    }
+
+    #[test]
+    fn add_field_works() {
+        let mut entry = Vec::new();
+        add_field(&mut entry, "MESSAGE", "one line");
+        add_field(&mut entry, "MESSAGE", "two\nlines");
+        assert_eq!(entry, b"MESSAGE=one line\nMESSAGE\n\x09\0\0\0\0\0\0\0two\nlines\n".to_vec());
+    }
+
+    #[test]
+    fn field_name_works() {
+        assert_eq!(field_name("user_id"), "USER_ID");
+        assert_eq!(field_name("http.status"), "HTTP_STATUS");
+        assert_eq!(field_name("_1st"), "ST");
+        assert_eq!(field_name("__"), "KEY");
+        assert_eq!(field_name("priority"), "KV_PRIORITY");
+        assert_eq!(field_name("message"), "KV_MESSAGE");
+    }
+
+    #[test]
+    fn send_works() {
+        let (server, journald, path) = bind("send");
+        journald.send(&log::Record::builder()
+            .level(log::Level::Warn)
+            .module_path(Some("mycrate::db"))
+            .file(Some("src/db.rs"))
+            .line(Some(42))
+            .key_values(&[("query_ms", 1200)])
+            .args(format_args!("slow query"))
+            .build()).unwrap();
+        let mut buf = [0u8; 1024];
+        let len = server.recv(&mut buf).unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&buf[..len]),
+            "PRIORITY=4\nMESSAGE=slow query\nCODE_FILE=src/db.rs\nCODE_LINE=42\n\
+             CODE_MODULE=mycrate::db\nSYSLOG_IDENTIFIER=app\nQUERY_MS=1200\n"
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reconnect_works() {
+        let (server, journald, path) = bind("reconnect");
+        drop(server);
+        fs::remove_file(&path).unwrap();
+        let server = UnixDatagram::bind(&path).unwrap();
+        journald.send(&log::Record::builder()
+            .level(log::Level::Info)
+            .args(format_args!("restarted"))
+            .build()).unwrap();
+        let mut buf = [0u8; 1024];
+        let len = server.recv(&mut buf).unwrap();
+        assert!(String::from_utf8_lossy(&buf[..len]).contains("MESSAGE=restarted\n"));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn send_failure_works() {
+        let (server, journald, path) = bind("failure");
+        drop(server);
+        fs::remove_file(&path).unwrap();
+        let record = log::Record::builder().level(log::Level::Error).build();
+        assert!(journald.send(&record).is_err());
+        let output = ::Output::Journald(journald.clone());
+        let logger = ::Logger::new().output(&log::Level::Error, output);
+        log::Log::log(&logger, &record);
+        log::Log::log(&logger, &record);
+        assert!(!journald.first_failure());
+    }
+
+    #[test]
+    fn send_memfd_works() {
+        let (server, journald, path) = bind("memfd");
+        let text = "x".repeat(16 * 1024 * 1024);
+        journald.send(&log::Record::builder()
+            .level(log::Level::Info)
+            .args(format_args!("{}", text))
+            .build()).unwrap();
+        let mut file = recv_fd(&server);
+        // The memory file is shared with the sender, so it is read from the start of the file
+        // instead of the offset after writing it.
+        file.seek(SeekFrom::Start(0)).unwrap();
+        let mut entry = String::new();
+        file.read_to_string(&mut entry).unwrap();
+        assert!(entry == format!("PRIORITY=6\nMESSAGE={}\nSYSLOG_IDENTIFIER=app\n", text));
+        fs::remove_file(&path).unwrap();
+    }
+}
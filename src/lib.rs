@@ -10,8 +10,8 @@
 //!
 //! The default configuration colorizes the "tag" portion of the log statement, where the tag is
 //! the text to the left of a separator, defaulted as the colon (`:`). The message is the
-//! portion to the right of the separator and it is _not_ ever colorized. The tag includes only the
-//! module path and the separator by default.
+//! portion to the right of the separator and it is _not_ colorized by default. The tag includes
+//! only the module path and the separator by default.
 //!
 //! ## Example
 //!
@@ -124,60 +124,423 @@ extern crate log;
 
 extern crate atty;
 extern crate ansi_term;
+extern crate chrono;
+#[cfg(feature = "gzip")]
+extern crate flate2;
+extern crate regex;
+extern crate unicode_width;
+#[cfg(unix)]
+extern crate libc;
+#[cfg(all(unix, feature = "signals"))]
+extern crate signal_hook;
 
 use log::{SetLoggerError};
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs::{File, OpenOptions};
 use std::io::{self, Write};
-use ansi_term::Colour;
+#[cfg(unix)]
+use std::os::unix::io::{FromRawFd, RawFd};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Instant;
+use ansi_term::{Colour, Style};
 
+mod background;
+mod filter;
+mod format;
+#[cfg(target_os = "linux")]
+mod journald;
+mod ratelimit;
+mod repeat;
+mod rotate;
+#[cfg(all(unix, feature = "signals"))]
+mod signals;
+mod syslog;
+mod template;
+pub mod testing;
+mod text;
+
+pub use background::QueueFull;
+pub use filter::MessageFilter;
+#[cfg(target_os = "linux")]
+pub use journald::Journald;
+pub use rotate::{Period, RotatingFile, Rotation};
+pub use syslog::{Facility, Syslog, SyslogFormat};
+pub use template::TemplateError;
+
+pub const DEFAULT_COLOR_CHOICE: ColorChoice = ColorChoice::Auto;
+#[deprecated(note = "colors are chosen with `DEFAULT_COLOR_CHOICE` instead")]
 pub const DEFAULT_COLORS: bool = true;
 pub const DEFAULT_DEBUG_COLOR: Colour = Colour::White;
 pub const DEFAULT_ERROR_COLOR: Colour = Colour::Red;
+pub const DEFAULT_FORMAT: Format = Format::Text;
 pub const DEFAULT_INCLUDE_LEVEL: bool = false;
 pub const DEFAULT_INCLUDE_LINE_NUMBERS: bool = false;
 pub const DEFAULT_INCLUDE_MODULE_PATH: bool = true;
+pub const DEFAULT_INCLUDE_THREAD_NAME: bool = false;
 pub const DEFAULT_INFO_COLOR: Colour = Colour::Green;
+pub const DEFAULT_KEY_VALUES: KeyValues = KeyValues::AfterMessage;
 pub const DEFAULT_LEVEL: log::Level = log::Level::Warn;
+pub const DEFAULT_MULTI_LINE: MultiLine = MultiLine::Off;
 pub const DEFAULT_OFFSET: u64 = 1;
+pub const DEFAULT_OVERFLOW: Overflow = Overflow::Off;
 pub const DEFAULT_SEPARATOR: &str = ": ";
+pub const DEFAULT_TIMESTAMP: Timestamp = Timestamp::Off;
 pub const DEFAULT_TRACE_COLOR: Colour = Colour::Purple;
 pub const DEFAULT_WARN_COLOR: Colour = Colour::Yellow;
 pub const MODULE_PATH_UNKNOWN: &str = "unknown";
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Output {
     Stderr,
     Stdout,
+    Writer(SharedWriter),
+    Syslog(Syslog),
+    #[cfg(target_os = "linux")]
+    Journald(Journald),
+    Capture(testing::Capture),
+}
+
+impl Output {
+    /// Creates an output to any writer, such as a socket or an in-memory buffer.
+    ///
+    /// Each log statement is written with a single call to the `write_all` method of the writer.
+    /// The output can be cloned to use the same writer for more than one level.
+    pub fn writer<W: Write + Send + 'static>(w: W) -> Output {
+        Output::Writer(SharedWriter {
+            writer: Arc::new(Mutex::new(Box::new(w))),
+            failed: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// Creates an output to a file.
+    ///
+    /// The file is created if it does not exist and log statements are appended to it if it
+    /// does.
+    pub fn file<P: AsRef<Path>>(path: P) -> io::Result<Output> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map(Output::writer)
+    }
+}
+
+#[cfg(unix)]
+impl FromRawFd for Output {
+    /// Creates an output to an already open file descriptor, such as one passed from a parent
+    /// process.
+    ///
+    /// The output takes ownership of the file descriptor and closes it when dropped.
+    unsafe fn from_raw_fd(fd: RawFd) -> Output {
+        Output::writer(File::from_raw_fd(fd))
+    }
+}
+
+/// A writer shared between the levels and clones of an output.
+///
+/// When a log statement cannot be written, such as when the disk is full, it is written to
+/// `stderr` instead, and the first failure is reported there.
+///
+/// Two shared writers are equal if they are the same writer.
+#[derive(Clone)]
+pub struct SharedWriter {
+    writer: Arc<Mutex<Box<dyn Write + Send>>>,
+    failed: Arc<AtomicBool>,
+}
+
+impl SharedWriter {
+    fn write_line(&self, line: &str) -> io::Result<()> {
+        let mut buf = String::with_capacity(line.len() + 1);
+        buf.push_str(line);
+        buf.push('\n');
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        writer.write_all(buf.as_bytes())
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        self.writer.lock().unwrap_or_else(|e| e.into_inner()).flush()
+    }
+
+    /// Checks if a failure to write is the first one, which is the only one that is reported.
+    fn first_failure(&self) -> bool {
+        !self.failed.swap(true, Ordering::Relaxed)
+    }
+}
+
+impl fmt::Debug for SharedWriter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SharedWriter({:p})", Arc::as_ptr(&self.writer))
+    }
+}
+
+impl PartialEq for SharedWriter {
+    fn eq(&self, other: &SharedWriter) -> bool {
+        Arc::ptr_eq(&self.writer, &other.writer)
+    }
+}
+
+impl Eq for SharedWriter {}
+
+/// An entry of the module path filters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ModuleFilter {
+    module: String,
+    prefix: bool,
+    exclude: bool,
+}
+
+impl ModuleFilter {
+    fn parse(filter: &str) -> ModuleFilter {
+        let (exclude, filter) = match filter.strip_prefix(['!', '-']) {
+            Some(f) => (true, f),
+            None => (false, filter),
+        };
+        let (prefix, filter) = match filter.strip_suffix('*') {
+            Some(f) => (true, f),
+            None => (false, filter.strip_suffix("::").unwrap_or(filter)),
+        };
+        ModuleFilter {
+            module: filter.to_string(),
+            prefix,
+            exclude,
+        }
+    }
+
+    fn matches(&self, module_path: &str) -> bool {
+        if self.prefix || self.module.is_empty() {
+            module_path.starts_with(&self.module)
+        } else {
+            module_matches(module_path, &self.module)
+        }
+    }
+}
+
+/// An error from parsing a string of level directives.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DirectiveError {
+    /// The directive has a level that is not one of `off`, `error`, `warn`, `info`, `debug`, or
+    /// `trace`.
+    InvalidLevel(String),
+    /// The directive has an empty module path, such as `=debug`.
+    EmptyModulePath(String),
+    /// The environment variable with the directives is not valid unicode.
+    NotUnicode(String),
+}
+
+impl fmt::Display for DirectiveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DirectiveError::InvalidLevel(ref d) => write!(f, "invalid level in directive '{}'", d),
+            DirectiveError::EmptyModulePath(ref d) => {
+                write!(f, "empty module path in directive '{}'", d)
+            },
+            DirectiveError::NotUnicode(ref v) => {
+                write!(f, "environment variable '{}' is not valid unicode", v)
+            },
+        }
+    }
+}
+
+impl Error for DirectiveError {}
+
+/// The format of a log statement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// The "tag", separator, and message, or the template if one is set.
+    Text,
+    /// A JSON object on a single line with the `time`, `level`, `target`, `module_path`, `file`,
+    /// `line`, `thread`, and `msg` keys, also known as JSON Lines.
+    Json,
+    /// Space-separated `key=value` pairs with the same keys as the JSON format, also known as
+    /// logfmt. Values with spaces, equal signs, quotes, or control characters are quoted and
+    /// escaped, and keys without a value, such as an unknown file, are left out. Those characters
+    /// are replaced with underscores in the keys of key-value pairs, and keys that would repeat
+    /// one of the other keys get a `kv_` prefix, such as `kv_msg` for `msg`.
+    Logfmt,
+}
+
+/// The placement of the key-value pairs of a log statement in the text format.
+///
+/// Key-value pairs are added to log statements with the key-value syntax of the `log` macros,
+/// such as `info!(user = id; "login")`. Machine-readable formats always include them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyValues {
+    /// The key-value pairs are left out.
+    Off,
+    /// The key-value pairs are written after the message, such as `app: login user=42`, with the
+    /// keys colorized with the color for the level.
+    AfterMessage,
+    /// The key-value pairs are written at the end of the "tag", such as `app user=42: login`.
+    InTag,
+}
+
+/// When to colorize the log statements written to `stdout` and `stderr`.
+///
+/// Other outputs, such as files and syslog, are never colorized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Colorizes the output if it is a terminal, unless the environment asks otherwise.
+    ///
+    /// Each stream is checked on its own when a log statement is written, so `stderr` is still
+    /// colorized when only `stdout` is piped, such as with `app | less`.
+    ///
+    /// The environment variables are checked in this order, and the first one that is set
+    /// decides:
+    ///
+    /// 1. `NO_COLOR` with any value that is not empty disables colors.
+    /// 2. `CLICOLOR_FORCE` with any value other than `0` enables colors, even if the output is not
+    ///    a terminal.
+    /// 3. `CLICOLOR` with the value `0` disables colors.
+    /// 4. `TERM` with the value `dumb` disables colors.
+    ///
+    /// Otherwise, the output is colorized if it is a terminal. The environment variables are
+    /// read when the logger is created, initialized, and updated with the handle.
+    Auto,
+    /// Always colorizes the output, regardless of the environment and terminal.
+    Always,
+    /// Never colorizes the output, regardless of the environment and terminal.
+    Never,
+}
+
+/// The layout of the continuation lines of a message with more than one line in the text format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MultiLine {
+    /// The continuation lines are written as they are, starting at the first column.
+    Off,
+    /// The continuation lines are indented with spaces to line up with the start of the message.
+    Indent,
+    /// The continuation lines start with the "tag" and the separator again, dimmed if colorizing
+    /// the output is enabled, so every line can be told apart from a new log statement.
+    RepeatTag,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+/// What to do with the lines of a log statement in the text format that are wider than the
+/// terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// The lines are written as they are, and the terminal wraps them.
+    Off,
+    /// The lines are wrapped between words, or within words that do not fit on a line, with the
+    /// wrapped lines indented to line up with the start of the message. If that leaves less than
+    /// 20 columns for the message, the indentation is shortened, down to two columns.
+    Wrap,
+    /// The lines are cut off at the width of the terminal, with an ellipsis (`…`) as the last
+    /// character.
+    Truncate,
+}
+
+/// The format of the timestamp in the "tag" portion of the log statement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timestamp {
+    /// No timestamp.
+    Off,
+    /// The wall-clock date and time in the RFC 3339 format in UTC, such as
+    /// `2018-02-03T14:05:09.123Z`.
+    Rfc3339Utc,
+    /// The wall-clock date and time in the RFC 3339 format with the local offset, such as
+    /// `2018-02-03T15:05:09.123+01:00`.
+    Rfc3339Local,
+    /// The local wall-clock time only, such as `15:05:09.123`.
+    Time,
+    /// The seconds since the logger was initialized, such as `12.345s`.
+    SinceInit,
+    /// The seconds since the previous log statement, or since the logger was initialized for the
+    /// first log statement, such as `+0.012s`.
+    SincePrevious,
+}
+
+/// The clock for the relative timestamps.
+#[derive(Debug, Clone)]
+struct Clock {
+    start: Instant,
+    previous: Arc<Mutex<Option<Instant>>>,
+}
+
+impl Clock {
+    fn new() -> Clock {
+        Clock {
+            start: Instant::now(),
+            previous: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+impl PartialEq for Clock {
+    fn eq(&self, _: &Clock) -> bool {
+        // The clock is state of a running logger and not part of its configuration.
+        true
+    }
+}
+
+/// A part of a log statement in the text format that can have its own style.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Component {
+    /// The level in the "tag", such as `ERROR`.
+    Level,
+    /// The module path in the "tag".
+    ModulePath,
+    /// The line number in the "tag", such as `(line 7)`.
+    LineNumber,
+    /// The separator between the "tag" and the message.
+    Separator,
+    /// The message, without the key-value pairs.
+    Message,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 struct Level {
     output: Output,
-    color: Colour,
+    style: Style,
+    components: Vec<(Component, Style)>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Logger {
-    colors: bool,
+    color_choice: ColorChoice,
+    env_colors: Option<bool>,
     include_level: bool,
     include_line_numbers: bool,
     include_module_path: bool,
+    include_thread_name: bool,
+    thread_colors: bool,
     level: log::Level,
     offset: u64,
     separator: String,
+    format: Format,
+    piped_format: Option<Format>,
+    key_values: KeyValues,
+    multi_line: MultiLine,
+    overflow: Overflow,
+    template: Option<template::Template>,
+    timestamp: Timestamp,
+    clock: Clock,
     verbosity: Option<u64>,
     error: Level,
     warn: Level,
     info: Level,
     debug: Level,
     trace: Level,
-    module_path_filters: Vec<String>,
+    module_path_filters: Vec<ModuleFilter>,
+    message_filters: Vec<MessageFilter>,
+    rate_limit: Option<ratelimit::RateLimiter>,
+    repeats: Option<repeat::Repeats>,
+    asynchronous: Option<(usize, QueueFull)>,
+    background: Option<background::Background>,
+    module_levels: Vec<(String, log::LevelFilter)>,
+    level_override: Option<log::LevelFilter>,
+    module_level_overrides: Vec<(String, log::LevelFilter)>,
 }
 
 impl Logger {
     /// Creates a new instance of the verbosity-based logger.
     ///
-    /// The default level is WARN. Color is enabled if the parent application or library is running
-    /// from a terminal, i.e. running a tty. The default separator is the ": " string. The default
+    /// The default level is WARN. Color is enabled for each of `stdout` and `stderr` that is a
+    /// terminal, i.e. a tty, unless the environment variables disable it as described for
+    /// `ColorChoice::Auto`. The default separator is the ": " string. The default
     /// output format is `module path: message`. The following default colors are used:
     ///
     /// | Level | Color         |
@@ -189,38 +552,86 @@ impl Logger {
     /// | Trace | Grey          |
     pub fn new() -> Logger {
         Logger {
-            colors: DEFAULT_COLORS && atty::is(atty::Stream::Stdout) && atty::is(atty::Stream::Stderr),
+            color_choice: DEFAULT_COLOR_CHOICE,
+            env_colors: env_colors(|v| env::var_os(v)),
             include_level: DEFAULT_INCLUDE_LEVEL,
             include_line_numbers: DEFAULT_INCLUDE_LINE_NUMBERS,
             include_module_path: DEFAULT_INCLUDE_MODULE_PATH,
+            include_thread_name: DEFAULT_INCLUDE_THREAD_NAME,
+            thread_colors: false,
             level: DEFAULT_LEVEL,
             offset: DEFAULT_OFFSET,
             separator: String::from(DEFAULT_SEPARATOR),
+            format: DEFAULT_FORMAT,
+            piped_format: None,
+            key_values: DEFAULT_KEY_VALUES,
+            multi_line: DEFAULT_MULTI_LINE,
+            overflow: DEFAULT_OVERFLOW,
+            template: None,
+            timestamp: DEFAULT_TIMESTAMP,
+            clock: Clock::new(),
             verbosity: None,
             error: Level {
                 output: Output::Stderr,
-                color: DEFAULT_ERROR_COLOR,
+                style: DEFAULT_ERROR_COLOR.normal(),
+                components: Vec::new(),
             },
             warn: Level {
                 output: Output::Stderr,
-                color: DEFAULT_WARN_COLOR,
+                style: DEFAULT_WARN_COLOR.normal(),
+                components: Vec::new(),
             },
             info: Level {
                 output: Output::Stderr,
-                color: DEFAULT_INFO_COLOR,
+                style: DEFAULT_INFO_COLOR.normal(),
+                components: Vec::new(),
             },
             debug: Level {
                 output: Output::Stderr,
-                color: DEFAULT_DEBUG_COLOR,
+                style: DEFAULT_DEBUG_COLOR.normal(),
+                components: Vec::new(),
             },
             trace: Level {
                 output: Output::Stderr,
-                color: DEFAULT_TRACE_COLOR,
+                style: DEFAULT_TRACE_COLOR.normal(),
+                components: Vec::new(),
             },
             module_path_filters: Vec::new(),
+            message_filters: Vec::new(),
+            rate_limit: None,
+            repeats: None,
+            asynchronous: None,
+            background: None,
+            module_levels: Vec::new(),
+            level_override: None,
+            module_level_overrides: Vec::new(),
         }
     }
 
+    /// Creates a new instance of the logger with levels from directives in an environment
+    /// variable.
+    ///
+    /// This is the same as using the `new` function followed by the `env_override` method.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::from_env("MYAPP_LOG")
+    ///         .expect("valid MYAPP_LOG directives")
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed to stderr");
+    /// }
+    /// ```
+    pub fn from_env(var: &str) -> Result<Logger, DirectiveError> {
+        Logger::new().env_override(var)
+    }
+
     /// Sets the color for a level.
     ///
     /// # Example
@@ -242,72 +653,84 @@ impl Logger {
     ///     error!("This is printed in light grey instead of bright red");
     /// }
     /// ```
-    pub fn color(mut self, l: &log::Level, c: Colour) -> Self {
-        match *l {
-            log::Level::Error => self.error.color = c,
-            log::Level::Warn => self.warn.color = c,
-            log::Level::Info => self.info.color = c,
-            log::Level::Debug => self.debug.color = c,
-            log::Level::Trace => self.trace.color = c,
-        }
-        self
+    pub fn color(self, l: &log::Level, c: Colour) -> Self {
+        self.style(l, c.normal())
     }
 
-    /// Sets the separator string.
+    /// Sets the style for a level, such as a bold color or a color with a background.
     ///
-    /// The separator is the string between the "tag" and the message that make up a log statement.
-    /// The tag will be colorized if enabled, while the message will not. The default is `: `.
-    ///
-    /// If the level, line numbers, and module path are all _not_ included in the log statement,
-    /// then the separator is changed to the empty string to avoid printing a lone string or
-    /// character before each message portion of the log statement.
+    /// The style is used for the "tag" and for the keys of key-value pairs. It replaces the color
+    /// from the `color` method.
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```
     /// #[macro_use] extern crate log;
     /// extern crate loggerv;
+    /// extern crate ansi_term;
+    ///
+    /// use log::Level;
+    /// use ansi_term::Colour;
     ///
     /// fn main() {
     ///     loggerv::Logger::new()
-    ///         .separator(" = ")
+    ///         .style(&Level::Error, Colour::Red.bold().underline())
     ///         .init()
     ///         .unwrap();
     ///
-    ///     error!("This is printed with an equal sign between the module path and this message");
+    ///     error!("This is printed in bold and underlined red");
     /// }
     /// ```
-    pub fn separator(mut self, s: &str) -> Self {
-        self.separator = String::from(s);
+    pub fn style(mut self, l: &log::Level, s: Style) -> Self {
+        self.level_mut(l).style = s;
         self
     }
 
-    /// Enables or disables colorizing the output.
+    /// Sets the style of a component of the log statement for a level.
     ///
-    /// If the logger is _not_ used in a terminal, then the output is _not_ colorized regardless of
-    /// this value.
+    /// The level, module path, and line number in the "tag" use the style for the level unless
+    /// they have their own style, while the separator and the message are not styled unless they
+    /// have their own style. Styles are only used if colorizing the output is enabled.
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```
     /// #[macro_use] extern crate log;
     /// extern crate loggerv;
+    /// extern crate ansi_term;
+    ///
+    /// use log::Level;
+    /// use ansi_term::{Colour, Style};
+    /// use loggerv::Component;
     ///
     /// fn main() {
     ///     loggerv::Logger::new()
-    ///         .colors(false)
+    ///         .level(true)
+    ///         .style(&Level::Error, Colour::Red.bold())
+    ///         .component_style(&Level::Error, Component::ModulePath, Style::new().dimmed())
+    ///         .component_style(&Level::Debug, Component::Message, Style::new().dimmed())
     ///         .init()
     ///         .unwrap();
     ///
-    ///     error!("This is printed without any colorization");
+    ///     error!("This is printed with 'ERROR' in bold red and a dimmed module path");
     /// }
     /// ```
-    pub fn colors(mut self, c: bool) -> Self {
-        self.colors = c && atty::is(atty::Stream::Stdout) && atty::is(atty::Stream::Stderr);
+    pub fn component_style(mut self, l: &log::Level, c: Component, s: Style) -> Self {
+        let level = self.level_mut(l);
+        level.components.retain(|&(component, _)| component != c);
+        level.components.push((c, s));
         self
     }
 
-    /// Enables colorizing the output regardless if logger is used in a terminal or not.
+    /// Sets the separator string.
+    ///
+    /// The separator is the string between the "tag" and the message that make up a log statement.
+    /// The tag will be colorized if enabled, while the message will not unless it has a style from
+    /// the `component_style` method. The default is `: `.
+    ///
+    /// If the timestamp, level, line numbers, and module path are all _not_ included in the log
+    /// statement, then the separator is left out to avoid printing a lone string or character
+    /// before each message portion of the log statement.
     ///
     /// # Example
     ///
@@ -317,22 +740,24 @@ impl Logger {
     ///
     /// fn main() {
     ///     loggerv::Logger::new()
-    ///         .force_colors()
+    ///         .separator(" = ")
     ///         .init()
     ///         .unwrap();
     ///
-    ///     error!("This is always printed with colorization");
+    ///     error!("This is printed with an equal sign between the module path and this message");
     /// }
     /// ```
-    pub fn force_colors(mut self) -> Self {
-        self.colors = true;
+    pub fn separator(mut self, s: &str) -> Self {
+        self.separator = String::from(s);
         self
     }
 
-    /// Disables colorizing the output.
+    /// Sets the placement of the key-value pairs of log statements in the text format.
     ///
-    /// The default is to colorize the output unless `stdout` and `stderr` are redirected or piped,
-    /// i.e. not a tty.
+    /// The default is to write the key-value pairs after the message as `key=value`, where the
+    /// value is quoted if it contains spaces, quotes, equal signs, or control characters.
+    /// Machine-readable formats always include the key-value pairs as fields, and a template
+    /// includes them with the `kv` field.
     ///
     /// # Example
     ///
@@ -340,23 +765,29 @@ impl Logger {
     /// #[macro_use] extern crate log;
     /// extern crate loggerv;
     ///
+    /// use loggerv::KeyValues;
+    ///
     /// fn main() {
     ///     loggerv::Logger::new()
-    ///         .no_colors()
+    ///         .key_values(KeyValues::InTag)
     ///         .init()
     ///         .unwrap();
     ///
-    ///     error!("This is printed without any colorization");
+    ///     error!(user = 42; "This is printed with 'user=42' in the tag");
     /// }
     /// ```
-    pub fn no_colors(mut self) -> Self {
-        self. colors = false;
+    pub fn key_values(mut self, k: KeyValues) -> Self {
+        self.key_values = k;
         self
     }
 
-    /// Enables or disables including line numbers in the "tag" portion of the log statement.
+    /// Sets the layout of the continuation lines of messages with more than one line, such as error
+    /// reports and configuration dumps.
     ///
-    /// The tag is the text to the left of the separator.
+    /// The default is to write the continuation lines as they are, which makes them look like log
+    /// statements without a "tag". The indentation is the width of the tag and the separator as
+    /// they are shown on a terminal, without the escape sequences for the colors. This has no
+    /// effect on templates, on machine-readable formats, or if there is no tag.
     ///
     /// # Example
     ///
@@ -364,26 +795,30 @@ impl Logger {
     /// #[macro_use] extern crate log;
     /// extern crate loggerv;
     ///
+    /// use loggerv::MultiLine;
+    ///
     /// fn main() {
     ///     loggerv::Logger::new()
-    ///         .line_numbers(true)
+    ///         .multi_line(MultiLine::Indent)
     ///         .init()
     ///         .unwrap();
     ///
-    ///     error!("This is printed with the module path and the line number surrounded by
-    ///     parentheses");
+    ///     error!("This is printed with the second line\nlined up with the first line");
     /// }
     /// ```
-    pub fn line_numbers(mut self, i: bool) -> Self {
-        self.include_line_numbers = i;
+    pub fn multi_line(mut self, m: MultiLine) -> Self {
+        self.multi_line = m;
         self
     }
 
-    /// Enables or disables including the level in the log statement's tag portion. The tag of the
-    /// log statement is the text to the left of the separator.
+    /// Sets what to do with the lines of log statements that are wider than the terminal.
     ///
-    /// If the level and the module path are both inculded, then the module path is surrounded by
-    /// square brackets.
+    /// The width of the terminal is checked for `stdout` and `stderr` on their own when a log
+    /// statement is written, and the `COLUMNS` environment variable overrides it. Streams that are
+    /// not terminals and the other outputs are never wrapped or truncated. The width of the text
+    /// is measured in terminal columns, so wide characters, such as CJK characters, count as two
+    /// columns, and the escape sequences for the colors are ignored. The default is to leave the
+    /// lines as they are. This has no effect on machine-readable formats.
     ///
     /// # Example
     ///
@@ -391,22 +826,41 @@ impl Logger {
     /// #[macro_use] extern crate log;
     /// extern crate loggerv;
     ///
+    /// use loggerv::Overflow;
+    ///
     /// fn main() {
     ///     loggerv::Logger::new()
-    ///         .level(true)
+    ///         .overflow(Overflow::Wrap)
     ///         .init()
     ///         .unwrap();
     ///
-    ///     error!("This is printed with the 'ERROR' and the module path is surrounded in square
-    ///     brackets");
+    ///     error!("This is wrapped at the width of the terminal if it is too long to fit");
     /// }
     /// ```
-    pub fn level(mut self, i: bool) -> Self {
-        self.include_level = i;
+    pub fn overflow(mut self, o: Overflow) -> Self {
+        self.overflow = o;
         self
     }
 
-    /// Explicitly sets the log level instead of through a verbosity.
+    /// Sets a template for the layout of the log statement.
+    ///
+    /// The template replaces the "tag", separator, and message layout. It is text with fields in
+    /// curly braces, where the fields are `time`, `level`, `module`, `file`, `line`, `thread`,
+    /// `msg`, and `kv` for the key-value pairs as `key=value`. The `time` field uses the format
+    /// from the `timestamp` method, or the RFC 3339 format in UTC if the timestamp is off. Use `{{`
+    /// and `}}` for literal curly braces.
+    ///
+    /// A field name can be followed by a colon and a format with an optional fill character and
+    /// alignment (`<` for left, `>` for right, or `^` for center), an optional width, and optional
+    /// space-separated style words, such as `{level:>5 bold}` or `{module:.<20 dim}`. The style
+    /// words are `bold`, `dim`, `italic`, `underline`, `blink`, `reverse`, `hidden`,
+    /// `strikethrough`, a color (`black`, `red`, `green`, `yellow`, `blue`, `purple`, `cyan`, or
+    /// `white`), and a background color as a color prefixed with `on_`, such as `on_red`. Without
+    /// style words, the `level` field is colorized with the color for the level, the `thread` field
+    /// is colorized with the color for the thread if thread colors are enabled, and the other
+    /// fields are not colorized. Styles are only used if colorizing the output is enabled.
+    ///
+    /// The template is parsed once by this method, and an error is returned if it is invalid.
     ///
     /// # Example
     ///
@@ -416,31 +870,27 @@ impl Logger {
     ///
     /// fn main() {
     ///     loggerv::Logger::new()
-    ///         .max_level(log::Level::Info)
+    ///         .template("{time} {level:>5} {module}:{line} | {msg}")
+    ///         .unwrap()
     ///         .init()
     ///         .unwrap();
     ///
-    ///     error!("This is printed to stderr");
-    ///     warn!("This is printed to stderr");
-    ///     info!("This is printed to stdout");
-    ///     debug!("This is not printed to stdout");
-    ///     trace!("This is not printed to stdout");
+    ///     error!("This is printed with the time, level, module path, and line number");
     /// }
     /// ```
-    pub fn max_level(mut self, l: log::Level) -> Self {
-        self.level = l;
-        // It is important to set the Verbosity to None here because later with the `init` method,
-        // a `None` value indicates the verbosity has _not_ been set or overriden by using this
-        // method (`max_level`). If the verbosity is some value, then it will be used and the use
-        // of this method will be dismissed.
-        self.verbosity = None;
-        self
+    pub fn template(mut self, t: &str) -> Result<Self, TemplateError> {
+        self.template = Some(template::Template::parse(t)?);
+        Ok(self)
     }
 
-    /// Enables or disables including the module path in the "tag" portion of the log statement.
+    /// Sets the format of the log statements.
     ///
-    /// The tag is the text to the left of the separator. The default is to include the module
-    /// path. Ifthe level is also included, the module path is surrounded by square brackets.
+    /// The default is the text format with the "tag", separator, and message. The JSON format
+    /// writes each log statement as a JSON object on a single line for log shippers and other
+    /// tools, and the logfmt format writes it as `key=value` pairs for line-oriented tools such as
+    /// `grep` and `awk`. The `time` key is a timestamp in the RFC 3339 format in UTC, or with the
+    /// local offset if the timestamp is set to `Timestamp::Rfc3339Local`. Machine-readable formats
+    /// are never colorized.
     ///
     /// # Example
     ///
@@ -448,24 +898,27 @@ impl Logger {
     /// #[macro_use] extern crate log;
     /// extern crate loggerv;
     ///
+    /// use loggerv::Format;
+    ///
     /// fn main() {
     ///     loggerv::Logger::new()
-    ///         .module_path(false)
+    ///         .format(Format::Json)
     ///         .init()
     ///         .unwrap();
     ///
-    ///     error!("This is printed without leading module path and separator");
+    ///     error!("This is printed as a JSON object");
     /// }
     /// ```
-    pub fn module_path(mut self, i: bool) -> Self {
-        self.include_module_path = i;
+    pub fn format(mut self, f: Format) -> Self {
+        self.format = f;
         self
     }
 
-    /// Disables the module path in the "tag" portion of the log statement.
+    /// Sets the format of the log statements written to a stream that is _not_ a terminal.
     ///
-    /// The tag is the text to the left of the separator. The default is to include the module
-    /// path.
+    /// This replaces the format from the `format` method for `stdout` or `stderr` when it is
+    /// redirected or piped, i.e. not a tty. The same application can then write text when it is
+    /// used interactively and JSON when its output is collected by a log shipper.
     ///
     /// # Example
     ///
@@ -473,87 +926,81 @@ impl Logger {
     /// #[macro_use] extern crate log;
     /// extern crate loggerv;
     ///
+    /// use loggerv::Format;
+    ///
     /// fn main() {
     ///     loggerv::Logger::new()
-    ///         .no_module_path()
+    ///         .piped_format(Format::Json)
     ///         .init()
     ///         .unwrap();
     ///
-    ///     error!("This is printed without leading module path and separator");
+    ///     error!("This is printed as a JSON object if stderr is redirected");
     /// }
     /// ```
-    pub fn no_module_path(mut self) -> Self {
-        self.include_module_path = false;
+    pub fn piped_format(mut self, f: Format) -> Self {
+        self.piped_format = Some(f);
         self
     }
 
-    /// Sets the module path filter list. 
-    /// 
-    /// When any filter is matched as prefix of the log statement module path, the log
-    /// statement will be logged if log level allows.
-    /// Log statements not maching any filter will not be logged.
-    /// 
-    /// When not set (default) or set to empty Vec log statements will not be filtered 
-    /// by the module path.
-    /// 
+    /// Enables or disables colorizing the output.
+    ///
+    /// Enabling colors is the same as `ColorChoice::Auto`, so the output is _not_ colorized if the
+    /// logger is _not_ used in a terminal or the `NO_COLOR`, `CLICOLOR`, or `TERM` environment
+    /// variables disable colors. Disabling colors is the same as `ColorChoice::Never`.
+    ///
     /// # Example
-    /// Log only messages comming from this program.
-    /// 
+    ///
     /// ```rust
     /// #[macro_use] extern crate log;
     /// extern crate loggerv;
     ///
     /// fn main() {
     ///     loggerv::Logger::new()
-    ///         .module_path_filters(vec![module_path!().to_owned()])
+    ///         .colors(false)
     ///         .init()
     ///         .unwrap();
     ///
-    ///     error!("This is printed");
+    ///     error!("This is printed without any colorization");
     /// }
     /// ```
-    pub fn module_path_filters(mut self, filters: Vec<String>) -> Self {
-        self.module_path_filters = filters;
-        self
+    pub fn colors(self, c: bool) -> Self {
+        self.color_choice(if c { ColorChoice::Auto } else { ColorChoice::Never })
     }
 
-    /// Adds module path filter to the list of module path filters.
-    /// 
-    /// When any filter is matched as prefix of the log statement module path, the log
-    /// statement will be logged if log level allows.
-    /// Log statements not maching any filter will not be logged.
-    /// 
-    /// When not filters were added log statements will not be filtered 
-    /// by the module path.
-    /// 
+    /// Sets when to colorize the output.
+    ///
+    /// The default is `ColorChoice::Auto`, which follows the `NO_COLOR`, `CLICOLOR_FORCE`,
+    /// `CLICOLOR`, and `TERM` conventions for the environment variables before checking if the
+    /// output is a terminal. The `colors`, `force_colors`, and `no_colors` methods also set the
+    /// color choice, so the last of these methods that is called wins. `ColorChoice::Always` and
+    /// `ColorChoice::Never` are explicit choices of the application, so they win over the
+    /// environment variables.
+    ///
     /// # Example
-    /// Log only messages comming from this program.
-    /// 
+    ///
     /// ```rust
     /// #[macro_use] extern crate log;
     /// extern crate loggerv;
     ///
+    /// use loggerv::ColorChoice;
+    ///
     /// fn main() {
     ///     loggerv::Logger::new()
-    ///         .add_module_path_filter(module_path!())
+    ///         .color_choice(ColorChoice::Always)
     ///         .init()
     ///         .unwrap();
     ///
-    ///     error!("This is printed");
+    ///     error!("This is always printed with colorization, even if NO_COLOR is set");
     /// }
     /// ```
-    pub fn add_module_path_filter(mut self, filter: impl Into<String>) -> Self {
-        self.module_path_filters.push(filter.into());
+    pub fn color_choice(mut self, c: ColorChoice) -> Self {
+        self.color_choice = c;
         self
     }
 
-    /// Sets the base level.
+    /// Enables colorizing the output regardless if logger is used in a terminal or not.
     ///
-    /// The base level is the level used with zero (0) verbosity. The default is WARN. So, ERROR
-    /// and WARN statements will be written and INFO statements will be written with a verbosity of
-    /// 1 or greater. If the base level was changed to ERROR, then only ERROR statements will be
-    /// written and WARN statements will be written with a verbosity of 1 or greater. Use this
-    /// adjust the correlation of verbosity, i.e. number of `-v` occurrences, to level.
+    /// This is the same as `ColorChoice::Always`, which also ignores the environment variables.
     ///
     /// # Example
     ///
@@ -563,16 +1010,22 @@ impl Logger {
     ///
     /// fn main() {
     ///     loggerv::Logger::new()
-    ///         .base_level(log::Level::Error)
-    ///         .verbosity(0)
+    ///         .force_colors()
     ///         .init()
     ///         .unwrap();
     ///
-    ///     error!("This is printed");
-    ///     warn!("This is not printed");
-    ///     info!("This is not printed");
+    ///     error!("This is always printed with colorization");
     /// }
     /// ```
+    pub fn force_colors(self) -> Self {
+        self.color_choice(ColorChoice::Always)
+    }
+
+    /// Disables colorizing the output.
+    ///
+    /// The default is to colorize `stdout` and `stderr` unless the stream is redirected or piped,
+    /// i.e. not a tty, or the environment variables disable colors. This is the same as
+    /// `ColorChoice::Never`.
     ///
     /// # Example
     ///
@@ -582,32 +1035,20 @@ impl Logger {
     ///
     /// fn main() {
     ///     loggerv::Logger::new()
-    ///         .base_level(log::Level::Info)
-    ///         .verbosity(0)
+    ///         .no_colors()
     ///         .init()
     ///         .unwrap();
     ///
-    ///     error!("This is printed");
-    ///     warn!("This is also printed");
-    ///     info!("This is now printed, too");
+    ///     error!("This is printed without any colorization");
     /// }
     /// ```
-    pub fn base_level(mut self, b: log::Level) -> Self {
-        self.offset = match b {
-            log::Level::Error => 0,
-            log::Level::Warn => 1,
-            log::Level::Info => 2,
-            log::Level::Debug => 3,
-            log::Level::Trace => 4,
-
-        };
-        self
+    pub fn no_colors(self) -> Self {
+        self.color_choice(ColorChoice::Never)
     }
 
-    /// Sets the output for a level.
+    /// Enables or disables including line numbers in the "tag" portion of the log statement.
     ///
-    /// The output is either `stderr` or `stdout`. The default is for ERROR and WARN to be written
-    /// to `stderr` and INFO, DEBUG, and TRACE to `stdout`.
+    /// The tag is the text to the left of the separator.
     ///
     /// # Example
     ///
@@ -615,16 +1056,600 @@ impl Logger {
     /// #[macro_use] extern crate log;
     /// extern crate loggerv;
     ///
-    /// use log::Level;
-    /// use loggerv::Output;
-    ///
     /// fn main() {
     ///     loggerv::Logger::new()
-    ///         .output(&Level::Error, Output::Stdout)
-    ///         .output(&Level::Warn, Output::Stdout)
-    ///         .output(&Level::Info, Output::Stderr)
-    ///         .output(&Level::Debug, Output::Stderr)
-    ///         .output(&Level::Trace, Output::Stderr)
+    ///         .line_numbers(true)
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed with the module path and the line number surrounded by
+    ///     parentheses");
+    /// }
+    /// ```
+    pub fn line_numbers(mut self, i: bool) -> Self {
+        self.include_line_numbers = i;
+        self
+    }
+
+    /// Enables or disables including the name of the current thread in the "tag" portion of the log
+    /// statement, surrounded by angle brackets. The ID of the thread is used if it has no name.
+    ///
+    /// The tag is the text to the left of the separator.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .thread_name(true)
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed with the module path and '<main>'");
+    /// }
+    /// ```
+    pub fn thread_name(mut self, i: bool) -> Self {
+        self.include_thread_name = i;
+        self
+    }
+
+    /// Enables or disables colorizing the thread name in the "tag" portion of the log statement
+    /// with a color for each thread instead of the color for the level.
+    ///
+    /// The color is picked from the name of the thread, or its ID if it has no name, so a thread
+    /// keeps its color for the whole run. The default is to use the color for the level. This has
+    /// no effect unless the thread name is included and colorizing the output is enabled.
+    pub fn thread_colors(mut self, c: bool) -> Self {
+        self.thread_colors = c;
+        self
+    }
+
+    /// Enables or disables including the level in the log statement's tag portion. The tag of the
+    /// log statement is the text to the left of the separator.
+    ///
+    /// If the level and the module path are both inculded, then the module path is surrounded by
+    /// square brackets.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .level(true)
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed with the 'ERROR' and the module path is surrounded in square
+    ///     brackets");
+    /// }
+    /// ```
+    pub fn level(mut self, i: bool) -> Self {
+        self.include_level = i;
+        self
+    }
+
+    /// Sets the format of the timestamp in the "tag" portion of the log statement.
+    ///
+    /// The timestamp is the first part of the tag. The default is no timestamp.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// use loggerv::Timestamp;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .timestamp(Timestamp::Time)
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed with the time before the module path");
+    /// }
+    /// ```
+    pub fn timestamp(mut self, t: Timestamp) -> Self {
+        self.timestamp = t;
+        self
+    }
+
+    /// Explicitly sets the log level instead of through a verbosity.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .max_level(log::Level::Info)
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed to stderr");
+    ///     warn!("This is printed to stderr");
+    ///     info!("This is printed to stdout");
+    ///     debug!("This is not printed to stdout");
+    ///     trace!("This is not printed to stdout");
+    /// }
+    /// ```
+    pub fn max_level(mut self, l: log::Level) -> Self {
+        self.level = l;
+        // It is important to set the Verbosity to None here because later with the `init` method,
+        // a `None` value indicates the verbosity has _not_ been set or overriden by using this
+        // method (`max_level`). If the verbosity is some value, then it will be used and the use
+        // of this method will be dismissed.
+        self.verbosity = None;
+        self
+    }
+
+    /// Enables or disables including the module path in the "tag" portion of the log statement.
+    ///
+    /// The tag is the text to the left of the separator. The default is to include the module
+    /// path. Ifthe level is also included, the module path is surrounded by square brackets.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .module_path(false)
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed without leading module path and separator");
+    /// }
+    /// ```
+    pub fn module_path(mut self, i: bool) -> Self {
+        self.include_module_path = i;
+        self
+    }
+
+    /// Disables the module path in the "tag" portion of the log statement.
+    ///
+    /// The tag is the text to the left of the separator. The default is to include the module
+    /// path.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .no_module_path()
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed without leading module path and separator");
+    /// }
+    /// ```
+    pub fn no_module_path(mut self) -> Self {
+        self.include_module_path = false;
+        self
+    }
+
+    /// Sets the module path filter list.
+    ///
+    /// A filter is a module that allows the log statements from the module and its submodules,
+    /// so `foo` matches `foo` and `foo::bar` but not `foobar`. A filter that ends with `*` matches
+    /// any module path that starts with the rest of the filter instead, so `foo*` also matches
+    /// `foobar`. A filter that starts with `!` or `-` excludes the log statements that it matches,
+    /// such as `!tokio` or `-h2`.
+    ///
+    /// The filter that matches the longest part of the module path of a log statement decides if
+    /// the log statement is logged, and an exclusion wins over an allowing filter of the same
+    /// length. A log statement that no filter matches is logged if there are only exclusions, and
+    /// is not logged if there is any allowing filter. Log statements that pass the filters are
+    /// still subject to the levels.
+    ///
+    /// When not set (default) or set to empty Vec log statements will not be filtered
+    /// by the module path.
+    ///
+    /// # Example
+    ///
+    /// Log everything except the messages from two chatty crates, but keep the errors of the
+    /// connection pool of one of them.
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .module_path_filters(vec![
+    ///             String::from("!tokio"),
+    ///             String::from("-hyper"),
+    ///             String::from("hyper::client::pool"),
+    ///         ])
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed");
+    /// }
+    /// ```
+    pub fn module_path_filters(mut self, filters: Vec<String>) -> Self {
+        self.module_path_filters = filters.iter().map(|f| ModuleFilter::parse(f)).collect();
+        self
+    }
+
+    /// Adds module path filter to the list of module path filters.
+    ///
+    /// See the `module_path_filters` method for the syntax of a filter and the order in which the
+    /// filters are evaluated.
+    ///
+    /// When not filters were added log statements will not be filtered
+    /// by the module path.
+    ///
+    /// # Example
+    /// Log only messages comming from this program.
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .add_module_path_filter(module_path!())
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed");
+    /// }
+    /// ```
+    pub fn add_module_path_filter(mut self, filter: impl Into<String>) -> Self {
+        self.module_path_filters.push(ModuleFilter::parse(&filter.into()));
+        self
+    }
+
+    /// Adds a filter on the message of log statements with a regular expression.
+    ///
+    /// The message filters are checked after the levels and the module path filters, so they
+    /// can drop known messages from a module without losing its other messages. See the
+    /// `MessageFilter` type for how the filters are combined.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// use loggerv::MessageFilter;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .message_filter(MessageFilter::exclude("^deprecated option").unwrap())
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     warn!("deprecated option 'x' is ignored");
+    ///     error!("This is printed");
+    /// }
+    /// ```
+    pub fn message_filter(mut self, f: MessageFilter) -> Self {
+        self.message_filters.push(f);
+        self
+    }
+
+    /// Limits the rate of the log statements from each callsite, which is the module path, file,
+    /// and line of a log statement.
+    ///
+    /// Each callsite can write a burst of log statements at once, and then a budget of log
+    /// statements every second. The other log statements are suppressed, and the number of
+    /// suppressed log statements is written as `suppressed N similar messages` with the level and
+    /// module path of the callsite once its window resets. Since nothing is written without a log
+    /// statement, that is with the next log statement from any callsite, or when the logger is
+    /// flushed, such as by the guard of the `shutdown_guard` method of the handle. A budget of
+    /// zero (0) suppresses everything after the burst until the logger is flushed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .rate_limit(10, 1)
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     for attempt in 0..1000 {
+    ///         warn!("Retrying, attempt {}", attempt);
+    ///     }
+    ///     log::logger().flush();
+    /// }
+    /// ```
+    pub fn rate_limit(mut self, burst: u32, per_second: u32) -> Self {
+        self.rate_limit = Some(ratelimit::RateLimiter::new(burst, per_second));
+        self
+    }
+
+    /// Enables or disables collapsing consecutive log statements with the same level, module path,
+    /// and message.
+    ///
+    /// A log statement that is the same as the previous one is not written again, but counted
+    /// instead. The count is written as `last message repeated N times` with the level and module
+    /// path of the repeated log statement when a different log statement is logged, or when the
+    /// logger is flushed, like syslog daemons do. The default is to write every log statement.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .collapse_repeats(true)
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     for _ in 0..100 {
+    ///         warn!("Waiting for the server");
+    ///     }
+    ///     error!("This is printed after 'last message repeated 99 times'");
+    /// }
+    /// ```
+    pub fn collapse_repeats(mut self, c: bool) -> Self {
+        self.repeats = if c { Some(repeat::Repeats::default()) } else { None };
+        self
+    }
+
+    /// Enables writing the log statements on a background thread.
+    ///
+    /// The log statements are formatted by the thread that logs them and sent to the background
+    /// thread through a queue that holds a number of log statements, which takes the latency of
+    /// the outputs off of the logging thread. When the queue is full, the policy decides if the
+    /// logging thread waits for room, or if a log statement is dropped. The number of dropped log
+    /// statements is written to `stderr`.
+    ///
+    /// The background thread is started by the `init` method, or by the `update` method of the
+    /// handle when the capacity or the policy is changed. Flushing the logger waits for the queued
+    /// log statements to be written. Use the `shutdown_guard` method of the handle to write the
+    /// queued log statements before the program exits. The log statements are written by the
+    /// logging thread if the background thread could not be started, or after it was stopped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// use loggerv::QueueFull;
+    ///
+    /// fn main() {
+    ///     let handle = loggerv::Logger::new()
+    ///         .asynchronous(1024, QueueFull::DropOldest)
+    ///         .init()
+    ///         .unwrap();
+    ///     let _guard = handle.shutdown_guard();
+    ///
+    ///     error!("This is printed by the background thread");
+    /// }
+    /// ```
+    pub fn asynchronous(mut self, capacity: usize, policy: QueueFull) -> Self {
+        self.asynchronous = Some((std::cmp::max(capacity, 1), policy));
+        self
+    }
+
+    /// Sets the level for log statements from a module and its submodules.
+    ///
+    /// The module is matched as a prefix of the log statement target on `::` boundaries, so
+    /// `mycrate` applies to `mycrate` and `mycrate::db`, but not to `mycrate_utils`. The target is
+    /// the module path unless it is set in the log statement, such as with
+    /// `info!(target: "audit", ...)`, in which case the level for `audit` applies. When several
+    /// modules match, the longest one wins, and when the same module is given more than once, the
+    /// last one wins. Log statements from modules without a matching entry use the level set with
+    /// the `verbosity` or `max_level` methods.
+    ///
+    /// The maximum level of the `log` crate is set to the most verbose of all levels at
+    /// initialization, so a module can be more verbose than the rest of the application.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// use log::LevelFilter;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .max_level(log::Level::Info)
+    ///         .module_level(module_path!(), LevelFilter::Trace)
+    ///         .module_level("hyper", LevelFilter::Warn)
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     trace!("This is printed because this module is set to TRACE");
+    /// }
+    /// ```
+    pub fn module_level(mut self, module: impl Into<String>, l: log::LevelFilter) -> Self {
+        self.module_levels.push((module.into(), l));
+        self
+    }
+
+    /// Sets levels from a string of comma-separated directives.
+    ///
+    /// Each directive is either a level, such as `info`, which sets the level for all modules, a
+    /// module path and a level separated by an equal sign, such as `hyper=warn`, which sets the
+    /// level for a module like the `module_level` method, or a lone module path, which sets the
+    /// level for the module to TRACE. The levels are `off`, `error`, `warn`, `info`, `debug`, and
+    /// `trace`, in any case.
+    ///
+    /// Directives take precedence over the rest of the builder regardless of the order of the
+    /// method calls: a level in the directives replaces the level from the `verbosity`,
+    /// `max_level`, and `base_level` methods, and a module path in the directives replaces the
+    /// same module path from the `module_level` method. Within the directives, the last directive
+    /// for a module path wins.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .verbosity(0)
+    ///         .directives("info,hyper=warn,rust_out=trace")
+    ///         .unwrap()
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     info!("This is printed because the directives replace the verbosity");
+    /// }
+    /// ```
+    pub fn directives(mut self, d: &str) -> Result<Self, DirectiveError> {
+        for directive in d.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            let mut parts = directive.splitn(2, '=');
+            let module = parts.next().unwrap_or("").trim();
+            match parts.next() {
+                Some(level) => {
+                    if module.is_empty() {
+                        return Err(DirectiveError::EmptyModulePath(directive.into()));
+                    }
+                    let level = level.trim().parse()
+                        .map_err(|_| DirectiveError::InvalidLevel(directive.into()))?;
+                    self.module_level_overrides.push((module.into(), level));
+                },
+                None => match module.parse() {
+                    Ok(level) => self.level_override = Some(level),
+                    Err(_) => {
+                        self.module_level_overrides.push((module.into(), log::LevelFilter::Trace))
+                    },
+                },
+            }
+        }
+        Ok(self)
+    }
+
+    /// Sets levels from directives in an environment variable.
+    ///
+    /// The value of the environment variable is parsed with the `directives` method, so it takes
+    /// precedence over the rest of the builder. Nothing is changed if the environment variable is
+    /// not set. This allows the verbosity of an application to be changed without new command
+    /// line arguments.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .verbosity(0)
+    ///         .env_override("MYAPP_LOG")
+    ///         .expect("valid MYAPP_LOG directives")
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     info!("This is printed with MYAPP_LOG=info");
+    /// }
+    /// ```
+    pub fn env_override(self, var: &str) -> Result<Self, DirectiveError> {
+        match env::var(var) {
+            Ok(d) => self.directives(&d),
+            Err(env::VarError::NotPresent) => Ok(self),
+            Err(env::VarError::NotUnicode(_)) => Err(DirectiveError::NotUnicode(var.into())),
+        }
+    }
+
+    /// Sets the base level.
+    ///
+    /// The base level is the level used with zero (0) verbosity. The default is WARN. So, ERROR
+    /// and WARN statements will be written and INFO statements will be written with a verbosity of
+    /// 1 or greater. If the base level was changed to ERROR, then only ERROR statements will be
+    /// written and WARN statements will be written with a verbosity of 1 or greater. Use this
+    /// adjust the correlation of verbosity, i.e. number of `-v` occurrences, to level.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .base_level(log::Level::Error)
+    ///         .verbosity(0)
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed");
+    ///     warn!("This is not printed");
+    ///     info!("This is not printed");
+    /// }
+    /// ```
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .base_level(log::Level::Info)
+    ///         .verbosity(0)
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed");
+    ///     warn!("This is also printed");
+    ///     info!("This is now printed, too");
+    /// }
+    /// ```
+    pub fn base_level(mut self, b: log::Level) -> Self {
+        self.offset = match b {
+            log::Level::Error => 0,
+            log::Level::Warn => 1,
+            log::Level::Info => 2,
+            log::Level::Debug => 3,
+            log::Level::Trace => 4,
+
+        };
+        self
+    }
+
+    /// Sets the output for a level.
+    ///
+    /// The output is `stderr`, `stdout`, a writer, such as a file or a `RotatingFile`, a syslog
+    /// daemon, the systemd journal on Linux, or a capture for tests. The default is for ERROR and
+    /// WARN to be written to `stderr` and INFO, DEBUG, and TRACE to `stdout`. Output to anything
+    /// other than `stderr` and `stdout` is never colorized. Syslog and the journal get the level as
+    /// the severity of the message in addition to the log statement.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// use log::Level;
+    /// use loggerv::Output;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .output(&Level::Error, Output::Stdout)
+    ///         .output(&Level::Warn, Output::Stdout)
+    ///         .output(&Level::Info, Output::Stderr)
+    ///         .output(&Level::Debug, Output::Stderr)
+    ///         .output(&Level::Trace, Output::Stderr)
     ///         .verbosity(0)
     ///         .init()
     ///         .unwrap();
@@ -636,6 +1661,31 @@ impl Logger {
     ///     trace!("This is printed on stderr instead of stdout");
     /// }
     /// ```
+    ///
+    /// # Example
+    ///
+    /// Write DEBUG and TRACE statements to a file, while the terminal only gets the rest.
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// use log::Level;
+    /// use loggerv::Output;
+    ///
+    /// fn main() {
+    ///     let file = Output::file(std::env::temp_dir().join("loggerv-debug.log")).unwrap();
+    ///     loggerv::Logger::new()
+    ///         .output(&Level::Debug, file.clone())
+    ///         .output(&Level::Trace, file)
+    ///         .verbosity(3)
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     info!("This is printed on stderr");
+    ///     debug!("This is written to the file");
+    /// }
+    /// ```
     pub fn output(mut self, l: &log::Level, o: Output) -> Self {
         match *l {
             log::Level::Error => self.error.output = o,
@@ -680,7 +1730,8 @@ impl Logger {
 
     /// Initializes the logger.
     ///
-    /// This also consumes the logger. It cannot be further modified after initialization.
+    /// This also consumes the logger. The returned handle can be used to change the configuration
+    /// of the logger after initialization, and it can be dropped if that is not needed.
     ///
     /// # Example
     ///
@@ -703,9 +1754,9 @@ impl Logger {
     ///
     /// # Example
     ///
-    /// If the tag will be empty because the level, line numbers, and module path were all
-    /// disabled, then the separator is changed to the empty string to avoid writing a long
-    /// character in front of each message for each log statement.
+    /// If the tag will be empty because the timestamp, level, line numbers, and module path were
+    /// all disabled, then the separator is left out to avoid writing a lone character in front of
+    /// each message for each log statement.
     ///
     ///
     /// ```rust
@@ -727,18 +1778,32 @@ impl Logger {
     ///     trace!("This is not printed to stdout");
     /// }
     /// ```
-    pub fn init(mut self) -> Result<(), SetLoggerError> {
-        // If there is no level, line number, or module path in the tag, then the tag will always
-        // be empty. The separator should also be empty so only the message component is printed
-        // for the log statement; otherwise, there is a weird floating colon in front of every log
-        // statement.
-        //
-        // It is better to do it here than in the `log` function because it only has to be
-        // determined once at initialization as opposed to every call to the `log` function. So
-        // a potentially slight performance improvement.
-        if !self.include_level && !self.include_line_numbers && !self.include_module_path {
-            self.separator = String::new();
+    pub fn init(mut self) -> Result<LoggerHandle, SetLoggerError> {
+        self.resolve_verbosity();
+        self.env_colors = env_colors(|v| env::var_os(v));
+        self.clock = Clock::new();
+        log::set_max_level(self.max_level_filter());
+        let logger = Arc::new(RwLock::new(self));
+        log::set_boxed_logger(Box::new(InstalledLogger(logger.clone())))?;
+        logger.write().unwrap_or_else(|e| e.into_inner()).start_background();
+        Ok(LoggerHandle { logger })
+    }
+
+    /// Starts a background thread if the capacity or the policy of the `asynchronous` method has
+    /// changed, after stopping the previous one.
+    fn start_background(&mut self) {
+        if self.background.as_ref().map(background::Background::settings) == self.asynchronous {
+            return;
         }
+        if let Some(b) = self.background.take() {
+            b.stop();
+        }
+        self.background = self.asynchronous
+            .and_then(|(capacity, policy)| background::Background::start(capacity, policy).ok());
+    }
+
+    /// Sets the level from the verbosity and the offset if the verbosity is set.
+    fn resolve_verbosity(&mut self) {
         // The level is set based on verbosity only if the `verbosity` method has been used and
         // _not_ overwridden a later call to the `max_level` method. If neither the `verbosity` or
         // `max_level` method is used, then the `DEFAULT_LEVEL` is used because it is set with the
@@ -757,104 +1822,709 @@ impl Logger {
                 _ => log::Level::Trace,
             };
         }
-        log::set_max_level(self.level.to_level_filter());
-        log::set_boxed_logger(Box::new(self))
     }
 
-    /// Gets the color to use for the log statement's tag based on level.
-    fn select_color(&self, l: &log::Level) -> Colour {
+    /// Checks if the tag can have any text.
+    ///
+    /// If there is no timestamp, level, line number, or module path in the tag, then the tag will
+    /// always be empty. The separator is then left out so only the message component is printed
+    /// for the log statement; otherwise, there is a weird floating colon in front of every log
+    /// statement.
+    fn has_tag(&self) -> bool {
+        self.include_level || self.include_line_numbers || self.include_module_path
+            || self.include_thread_name || self.timestamp != Timestamp::Off
+    }
+
+    /// Gets the most verbose level of the global level and all of the module levels.
+    fn max_level_filter(&self) -> log::LevelFilter {
+        self.module_levels
+            .iter()
+            .map(|&(_, l)| l)
+            .chain(self.module_level_overrides.iter().map(|&(_, l)| l))
+            .fold(self.global_level(), std::cmp::max)
+    }
+
+    /// Gets the level for modules without a module level.
+    fn global_level(&self) -> log::LevelFilter {
+        self.level_override.unwrap_or_else(|| self.level.to_level_filter())
+    }
+
+    /// Gets the level for a target from the longest matching module level, or the global
+    /// level if no module level matches. Module levels from directives win over module levels
+    /// from the builder for the same module path.
+    fn select_level(&self, target: &str) -> log::LevelFilter {
+        let mut selected: Option<&(String, log::LevelFilter)> = None;
+        for entry in self.module_levels.iter().chain(&self.module_level_overrides) {
+            if module_matches(target, &entry.0)
+                && selected.map_or(true, |s| entry.0.len() >= s.0.len())
+            {
+                selected = Some(entry);
+            }
+        }
+        selected.map_or(self.global_level(), |&(_, l)| l)
+    }
+
+    /// Checks if the module path filters allow the log statements from a module path.
+    fn filter_module_path(&self, module_path: &str) -> bool {
+        let mut selected: Option<&ModuleFilter> = None;
+        for filter in &self.module_path_filters {
+            if filter.matches(module_path)
+                && selected.map_or(true, |s| {
+                    filter.module.len() > s.module.len()
+                        || (filter.module.len() == s.module.len() && filter.exclude)
+                })
+            {
+                selected = Some(filter);
+            }
+        }
+        match selected {
+            Some(filter) => !filter.exclude,
+            None => self.module_path_filters.iter().all(|f| f.exclude),
+        }
+    }
+
+    /// Gets the configuration for a level.
+    fn select(&self, l: &log::Level) -> &Level {
+        match *l {
+            log::Level::Error => &self.error,
+            log::Level::Warn => &self.warn,
+            log::Level::Info => &self.info,
+            log::Level::Debug => &self.debug,
+            log::Level::Trace => &self.trace,
+        }
+    }
+
+    /// Gets the configuration for a level to change it.
+    fn level_mut(&mut self, l: &log::Level) -> &mut Level {
+        match *l {
+            log::Level::Error => &mut self.error,
+            log::Level::Warn => &mut self.warn,
+            log::Level::Info => &mut self.info,
+            log::Level::Debug => &mut self.debug,
+            log::Level::Trace => &mut self.trace,
+        }
+    }
+
+    /// Gets the style to use for the log statement's tag based on level.
+    fn select_style(&self, l: &log::Level) -> Style {
+        self.select(l).style
+    }
+
+    /// Gets the style of a component of the log statement based on level. The separator and the
+    /// message have the default style, which is no style, unless they have their own style.
+    fn select_component_style(&self, l: &log::Level, c: Component) -> Style {
+        let level = self.select(l);
+        match level.components.iter().find(|&&(component, _)| component == c) {
+            Some(&(_, s)) => s,
+            None if c == Component::Separator || c == Component::Message => Style::default(),
+            None => level.style,
+        }
+    }
+
+    /// Gets the output stream to use for the level.
+    fn select_output(&self, l: &log::Level) -> &Output {
         match *l {
-            log::Level::Error => self.error.color,
-            log::Level::Warn => self.warn.color,
-            log::Level::Info => self.info.color,
-            log::Level::Debug => self.debug.color,
-            log::Level::Trace => self.trace.color,
+            log::Level::Error => &self.error.output,
+            log::Level::Warn => &self.warn.output,
+            log::Level::Info => &self.info.output,
+            log::Level::Debug => &self.debug.output,
+            log::Level::Trace => &self.trace.output,
+        }
+    }
+
+    /// Creates the timestamp text in a format for a log statement written now.
+    fn create_timestamp(&self, t: Timestamp) -> String {
+        match t {
+            Timestamp::Off => String::new(),
+            Timestamp::Rfc3339Utc => {
+                chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+            },
+            Timestamp::Rfc3339Local => {
+                chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, false)
+            },
+            Timestamp::Time => chrono::Local::now().format("%H:%M:%S%.3f").to_string(),
+            Timestamp::SinceInit => {
+                let elapsed = self.clock.start.elapsed();
+                format!("{}.{:03}s", elapsed.as_secs(), elapsed.subsec_millis())
+            },
+            Timestamp::SincePrevious => {
+                let now = Instant::now();
+                let mut previous = self.clock.previous.lock().unwrap_or_else(|e| e.into_inner());
+                let elapsed = now.duration_since(previous.unwrap_or(self.clock.start));
+                *previous = Some(now);
+                format!("+{}.{:03}s", elapsed.as_secs(), elapsed.subsec_millis())
+            },
+        }
+    }
+
+    /// Gets the format to use for an output stream.
+    fn select_format(&self, o: &Output) -> Format {
+        match self.piped_format {
+            Some(f) if !is_terminal(o) => f,
+            _ => self.format,
+        }
+    }
+
+    /// Checks if the log statements for an output stream are colorized.
+    fn use_colors(&self, o: &Output) -> bool {
+        match *o {
+            Output::Stderr | Output::Stdout => match self.color_choice {
+                ColorChoice::Always => true,
+                ColorChoice::Never => false,
+                ColorChoice::Auto => self.env_colors.unwrap_or_else(|| is_terminal(o)),
+            },
+            _ => false,
+        }
+    }
+
+    /// Creates the complete log statement in the format for an output stream.
+    fn create_line(&self, record: &log::Record, o: &Output) -> String {
+        let colors = self.use_colors(o);
+        match self.select_format(o) {
+            Format::Text => {
+                let (line, indent) = match self.template {
+                    Some(ref t) => (t.render(self, record, colors), 0),
+                    None => self.create_text(record, colors),
+                };
+                // Checking the width of the terminal takes system calls, so it is only done when
+                // the lines are fitted to it.
+                if self.overflow == Overflow::Off {
+                    line
+                } else {
+                    self.fit(line, indent, terminal_columns(o))
+                }
+            },
+            Format::Json => format::json(self, record),
+            Format::Logfmt => format::logfmt(self, record),
+        }
+    }
+
+    /// Creates the log statement in the text format without a template, along with the width of
+    /// the "tag" and the separator, which is where the message starts.
+    fn create_text(&self, record: &log::Record, colors: bool) -> (String, usize) {
+        let mut message = record.args().to_string();
+        let level = record.level();
+        let message_style = self.select_component_style(&level, Component::Message);
+        if colors && message_style != Style::default() {
+            // Each line is painted on its own to lay out the continuation lines.
+            message = message
+                .split('\n')
+                .map(|line| message_style.paint(line).to_string())
+                .collect::<Vec<_>>()
+                .join("\n");
+        }
+        if self.key_values == KeyValues::AfterMessage {
+            let kvs = format::key_values(record);
+            if !kvs.is_empty() {
+                message.push(' ');
+                message.push_str(&self.create_key_values(&kvs, level, colors));
+            }
+        }
+        if self.has_tag()
+            || (self.key_values == KeyValues::InTag && record.key_values().count() > 0)
+        {
+            let tag = self.create_tag(record, colors);
+            let message = self.continue_lines(&tag, &message, colors);
+            let indent = text::width(&tag) + text::width(&self.separator);
+            let style = self.select_component_style(&level, Component::Separator);
+            if colors && style != Style::default() {
+                let separator = style.paint(self.separator.as_str());
+                (format!("{}{}{}", tag, separator, message), indent)
+            } else {
+                (format!("{}{}{}", tag, self.separator, message), indent)
+            }
+        } else {
+            (message, 0)
+        }
+    }
+
+    /// Wraps or truncates the lines of a log statement in the text format that are wider than a
+    /// number of columns based on the configuration. The wrapped lines are indented by a number
+    /// of columns.
+    fn fit(&self, line: String, indent: usize, columns: Option<usize>) -> String {
+        match (self.overflow, columns) {
+            (Overflow::Wrap, Some(c)) => text::wrap(&line, c, indent),
+            (Overflow::Truncate, Some(c)) => text::truncate(&line, c),
+            _ => line,
+        }
+    }
+
+    /// Lays out the continuation lines of a message based on the configuration.
+    fn continue_lines(&self, tag: &str, message: &str, colors: bool) -> String {
+        let prefix = match self.multi_line {
+            MultiLine::Off => return message.to_string(),
+            MultiLine::Indent => " ".repeat(text::width(tag) + text::width(&self.separator)),
+            MultiLine::RepeatTag => {
+                let plain = text::strip_ansi(tag);
+                if colors {
+                    format!("{}{}", Style::new().dimmed().paint(plain), self.separator)
+                } else {
+                    format!("{}{}", plain, self.separator)
+                }
+            },
+        };
+        let mut lines = message.split('\n');
+        let mut text = lines.next().unwrap_or_default().to_string();
+        for line in lines {
+            text.push('\n');
+            text.push_str(&prefix);
+            text.push_str(line);
         }
+        text
     }
 
-    /// Gets the output stream to use for the level.
-    fn select_output(&self, l: &log::Level) -> Output {
-        match *l {
-            log::Level::Error => self.error.output,
-            log::Level::Warn => self.warn.output,
-            log::Level::Info => self.info.output,
-            log::Level::Debug => self.debug.output,
-            log::Level::Trace => self.trace.output,
+    /// Creates the `key=value` text for key-value pairs, with the keys colorized if enabled.
+    fn create_key_values(&self, kvs: &[format::KeyValue], l: log::Level, colors: bool) -> String {
+        let mut text = String::new();
+        for kv in kvs {
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            let key = format::logfmt_key(&kv.key);
+            if colors {
+                text.push_str(&self.select_style(&l).paint(key).to_string());
+            } else {
+                text.push_str(&key);
+            }
+            text.push('=');
+            format::logfmt_value(&mut text, &kv.value);
         }
+        text
     }
 
     /// Creates the tag portion of the log statement based on the configuration.
     ///
     /// The tag portion is the of the log statement is the text to the left of the separator, while
     /// the text to the right of the separator is the message.
-    fn create_tag(&self, record: &log::Record) -> String {
+    fn create_tag(&self, record: &log::Record, colors: bool) -> String {
         let level = record.level();
-        let level_text = if self.include_level {
-            level.to_string()
-        } else {
-            String::new()
-        };
-
-        let module_path_text = if self.include_module_path {
+        let style = self.select_style(&level);
+        let mut parts = Vec::new();
+        parts.push((self.create_timestamp(self.timestamp), style));
+        if self.include_level {
+            parts.push((level.to_string(), self.select_component_style(&level, Component::Level)));
+        }
+        if self.include_module_path {
             let pth = record.module_path().unwrap_or(MODULE_PATH_UNKNOWN);
-            if self.include_level {
-                format!(" [{}]", pth)
+            let module_path_text = if self.include_level {
+                format!("[{}]", pth)
             } else {
                 pth.into()
-            }
-        } else {
-            String::new()
-        };
-        let line_text = if self.include_line_numbers {
+            };
+            let module_path_style = self.select_component_style(&level, Component::ModulePath);
+            parts.push((module_path_text, module_path_style));
+        }
+        if self.include_line_numbers {
             if let Some(l) = record.line() {
-                format!(" (line {})", l)
+                let line_style = self.select_component_style(&level, Component::LineNumber);
+                parts.push((format!("(line {})", l), line_style));
+            }
+        }
+        if self.include_thread_name {
+            let name = format::thread_name();
+            let thread_style = if self.thread_colors {
+                thread_color(&name).normal()
             } else {
-                String::new()
+                style
+            };
+            parts.push((format!("<{}>", name), thread_style));
+        }
+        if self.key_values == KeyValues::InTag {
+            parts.push((self.create_key_values(&format::key_values(record), level, false), style));
+        }
+
+        // Neighboring parts with the same style are painted together to keep the escape sequences
+        // to a minimum.
+        let mut painted: Vec<(String, Style)> = Vec::new();
+        for (text, style) in parts {
+            if text.is_empty() {
+                continue;
+            }
+            match painted.last_mut() {
+                Some(last) if last.1 == style => {
+                    last.0.push(' ');
+                    last.0.push_str(&text);
+                },
+                _ => painted.push((text, style)),
             }
-        } else {
-            String::new()
-        };
-        let mut tag = format!("{}{}{}", level_text, module_path_text, line_text);
-        if self.colors {
-            tag = self.select_color(&level).paint(tag).to_string();
         }
-        tag
+        painted
+            .into_iter()
+            .map(|(text, style)| if colors { style.paint(text).to_string() } else { text })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Writes a log statement that passed the levels and filters to its output.
+    fn write(&self, record: &log::Record) {
+        let output = self.select_output(&record.level());
+        let line = self.create_line(record, output);
+        if let Some(ref b) = self.background {
+            if b.send(output, record, &line) {
+                return;
+            }
+        }
+        write_output(output, record, &line);
+    }
+
+    /// Writes the number of log statements suppressed at a callsite by the rate limit.
+    fn write_suppressed(&self, s: &ratelimit::Suppressed) {
+        let message = format_args!("suppressed {} similar messages", s.count);
+        self.write_note(s.level, &s.target, &s.callsite, message);
+    }
+
+    /// Writes the number of times a log statement was repeated.
+    fn write_repeated(&self, r: &repeat::Repeated) {
+        let message = format_args!("last message repeated {} times", r.count);
+        self.write_note(r.level, &r.target, &r.callsite, message);
+    }
+
+    /// Writes a log statement of the logger itself about log statements from a callsite.
+    fn write_note(&self, l: log::Level, target: &str, c: &ratelimit::Callsite, m: fmt::Arguments) {
+        self.write(&log::Record::builder()
+            .level(l)
+            .target(target)
+            .module_path(c.module_path.as_deref())
+            .file(c.file.as_deref())
+            .line(c.line)
+            .args(m)
+            .build());
     }
 }
 
 impl log::Log for Logger {
     fn enabled(&self, metadata: &log::Metadata) -> bool {
-        metadata.level() <= self.level
+        metadata.level() <= self.select_level(metadata.target())
     }
 
     fn log(&self, record: &log::Record) {
         if self.enabled(record.metadata()) {
-            if !self.module_path_filters.is_empty() && !self.module_path_filters.iter().any(|filter| record.module_path().unwrap_or(MODULE_PATH_UNKNOWN).starts_with(filter)) {
-                return
+            if !self.filter_module_path(record.module_path().unwrap_or(MODULE_PATH_UNKNOWN)) {
+                return;
             }
-            match self.select_output(&record.level()) {
-                Output::Stderr => {
-                    writeln!(
-                        &mut io::stderr(),
-                        "{}{}{}",
-                        self.create_tag(&record),
-                        self.separator,
-                        record.args()
-                    ).expect("Writing to stderr");
-                },
-                Output::Stdout => {
-                    println!(
-                        "{}{}{}",
-                        self.create_tag(&record),
-                        self.separator,
-                        record.args()
-                    );
-                },
+            if !filter::allows(&self.message_filters, record) {
+                return;
+            }
+            if let Some(ref repeats) = self.repeats {
+                match repeats.check(record) {
+                    repeat::Seen::Repeat => return,
+                    repeat::Seen::New(Some(r)) => self.write_repeated(&r),
+                    repeat::Seen::New(None) => {},
+                }
+            }
+            if let Some(ref limiter) = self.rate_limit {
+                let now = Instant::now();
+                for suppressed in limiter.take_expired(now) {
+                    self.write_suppressed(&suppressed);
+                }
+                match limiter.check(record, now) {
+                    None => return,
+                    Some(0) => {},
+                    Some(count) => self.write_suppressed(&ratelimit::Suppressed {
+                        callsite: ratelimit::Callsite::of(record),
+                        level: record.level(),
+                        target: record.target().to_string(),
+                        count,
+                    }),
+                }
+            }
+            self.write(record);
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(r) = self.repeats.as_ref().and_then(repeat::Repeats::take) {
+            self.write_repeated(&r);
+        }
+        if let Some(ref limiter) = self.rate_limit {
+            for suppressed in limiter.take_suppressed() {
+                self.write_suppressed(&suppressed);
+            }
+        }
+        if let Some(ref b) = self.background {
+            b.wait_idle();
+        }
+        // println! flushes by itself, so only the writers need to be flushed.
+        for level in &[&self.error, &self.warn, &self.info, &self.debug, &self.trace] {
+            if let Output::Writer(ref w) = level.output {
+                if let Err(e) = w.flush() {
+                    if w.first_failure() {
+                        let _ = writeln!(io::stderr(), "loggerv: flushing failed: {}", e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Writes the text of a log statement to an output.
+fn write_output(o: &Output, record: &log::Record, line: &str) {
+    match *o {
+        Output::Stderr => {
+            writeln!(&mut io::stderr(), "{}", line).expect("Writing to stderr");
+        },
+        Output::Stdout => {
+            println!("{}", line);
+        },
+        Output::Writer(ref w) => {
+            if let Err(e) = w.write_line(line) {
+                write_fallback("the output", w.first_failure(), &e, line);
             }
+        },
+        Output::Syslog(ref s) => {
+            if let Err(e) = s.send(record.level(), line) {
+                write_fallback("syslog", s.first_failure(), &e, line);
+            }
+        },
+        #[cfg(target_os = "linux")]
+        Output::Journald(ref j) => {
+            if let Err(e) = j.send(record) {
+                write_fallback("the journal", j.first_failure(), &e, line);
+            }
+        },
+        Output::Capture(ref c) => {
+            c.push(record, line);
+        },
+    }
+}
+
+/// Writes the text of a log statement to `stderr` instead of an output that failed, since a
+/// failing output must not crash the program. Only the first failure of an output is reported.
+fn write_fallback(name: &str, report: bool, e: &io::Error, line: &str) {
+    let mut stderr = io::stderr();
+    if report {
+        let _ = writeln!(
+            stderr,
+            "loggerv: sending to {} failed, writing to stderr instead: {}",
+            name,
+            e
+        );
+    }
+    let _ = writeln!(stderr, "{}", line);
+}
+
+/// Checks if an output stream is a terminal.
+fn is_terminal(o: &Output) -> bool {
+    match *o {
+        Output::Stderr => atty::is(atty::Stream::Stderr),
+        Output::Stdout => atty::is(atty::Stream::Stdout),
+        _ => false,
+    }
+}
+
+/// Gets the width of the terminal for an output stream in columns, or `None` if it is not a
+/// terminal. The `COLUMNS` environment variable overrides the width of the terminal.
+fn terminal_columns(o: &Output) -> Option<usize> {
+    if !is_terminal(o) {
+        return None;
+    }
+    env::var("COLUMNS")
+        .ok()
+        .and_then(|c| c.parse().ok())
+        .filter(|&c| c > 0)
+        .or_else(|| stream_columns(o))
+}
+
+/// Asks the terminal of an output stream for its width in columns.
+#[cfg(unix)]
+fn stream_columns(o: &Output) -> Option<usize> {
+    let fd = match *o {
+        Output::Stderr => libc::STDERR_FILENO,
+        Output::Stdout => libc::STDOUT_FILENO,
+        _ => return None,
+    };
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    if unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &mut size) } == 0 && size.ws_col > 0 {
+        Some(usize::from(size.ws_col))
+    } else {
+        None
+    }
+}
+
+#[cfg(not(unix))]
+fn stream_columns(_: &Output) -> Option<usize> {
+    None
+}
+
+/// Picks a color for a thread from its name, which is the same for every run.
+fn thread_color(name: &str) -> Colour {
+    // Colors of the 256-color palette that are readable on dark and light backgrounds and are not
+    // used for the levels.
+    const PALETTE: [u8; 8] = [33, 37, 64, 97, 130, 135, 166, 169];
+    // FNV-1a, since the hash of the standard library is not guaranteed to be stable.
+    let hash = name.bytes().fold(0xcbf2_9ce4_8422_2325u64, |h, b| {
+        (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    });
+    Colour::Fixed(PALETTE[(hash % PALETTE.len() as u64) as usize])
+}
+
+/// Checks if the environment variables enable or disable colors, or `None` if they leave it to
+/// the terminal check.
+fn env_colors<F: Fn(&str) -> Option<std::ffi::OsString>>(var: F) -> Option<bool> {
+    if var("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+        Some(false)
+    } else if var("CLICOLOR_FORCE").is_some_and(|v| !v.is_empty() && v != "0") {
+        Some(true)
+    } else if var("CLICOLOR").is_some_and(|v| v == "0")
+        || var("TERM").is_some_and(|v| v == "dumb")
+    {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Checks if a module path is the module or one of the submodules of another module.
+fn module_matches(module_path: &str, module: &str) -> bool {
+    module_path.starts_with(module)
+        && (module_path.len() == module.len() || module_path[module.len()..].starts_with("::"))
+}
+
+/// A handle to change the configuration of an initialized logger.
+///
+/// The handle is returned by the `init` method. Changes are thread-safe and take effect for the
+/// next log statement, and the maximum level of the `log` crate is kept in sync with them. Clones
+/// of the handle change the same logger.
+///
+/// # Example
+///
+/// ```rust
+/// #[macro_use] extern crate log;
+/// extern crate loggerv;
+///
+/// fn main() {
+///     let handle = loggerv::Logger::new()
+///         .verbosity(0)
+///         .init()
+///         .unwrap();
+///
+///     debug!("This is not printed");
+///     handle.set_max_level(log::Level::Debug);
+///     debug!("This is printed after switching to a debug mode");
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct LoggerHandle {
+    logger: Arc<RwLock<Logger>>,
+}
+
+impl LoggerHandle {
+    /// Changes the logger with the Builder pattern API.
+    ///
+    /// The function gets a copy of the current configuration and returns the new configuration,
+    /// so any of the configuration methods can be used at run-time. The logger keeps the current
+    /// configuration while the function runs, so the function can log, but when several threads
+    /// update the logger at once, the last one to finish wins.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     let handle = loggerv::Logger::new().init().unwrap();
+    ///     handle.update(|logger| logger.line_numbers(true).level(true));
+    ///
+    ///     error!("This is printed with the level and the line number");
+    /// }
+    /// ```
+    pub fn update<F: FnOnce(Logger) -> Logger>(&self, f: F) {
+        let current = self.logger.read().unwrap_or_else(|e| e.into_inner()).clone();
+        let mut updated = f(current);
+        updated.resolve_verbosity();
+        updated.env_colors = env_colors(|v| env::var_os(v));
+        updated.start_background();
+        let mut logger = self.logger.write().unwrap_or_else(|e| e.into_inner());
+        log::set_max_level(updated.max_level_filter());
+        *logger = updated;
+    }
+
+    /// Changes the level like the `max_level` method, replacing the level from the `directives`
+    /// and `from_env` methods.
+    pub fn set_max_level(&self, l: log::Level) {
+        self.update(|mut logger| {
+            logger.level_override = None;
+            logger.max_level(l)
+        })
+    }
+
+    /// Changes the level based on a verbosity like the `verbosity` method, replacing the level
+    /// from the `directives` and `from_env` methods.
+    pub fn set_verbosity(&self, v: u64) {
+        self.update(|mut logger| {
+            logger.level_override = None;
+            logger.verbosity(v)
+        })
+    }
+
+    /// Changes the level for a module like the `module_level` method, replacing any level that
+    /// was set for the same module with the `module_level`, `directives`, and `from_env` methods.
+    pub fn set_module_level(&self, module: impl Into<String>, l: log::LevelFilter) {
+        let module = module.into();
+        self.update(|mut logger| {
+            logger.module_levels.retain(|(m, _)| *m != module);
+            logger.module_level_overrides.retain(|(m, _)| *m != module);
+            logger.module_level(module, l)
+        })
+    }
+
+    /// Enables or disables colorizing the output like the `colors` method.
+    pub fn set_colors(&self, c: bool) {
+        self.update(|logger| logger.colors(c))
+    }
+
+    /// Changes the output for a level like the `output` method.
+    pub fn set_output(&self, l: &log::Level, o: Output) {
+        self.update(|logger| logger.output(l, o))
+    }
+
+    /// Creates a guard that flushes the logger and stops the background thread of the
+    /// `asynchronous` method when it is dropped, after the queued log statements are written.
+    ///
+    /// Keep the guard in a variable until the end of the `main` function, since log statements
+    /// that are still queued when the program exits are lost. Log statements after the guard is
+    /// dropped are written by the logging thread, until the `asynchronous` method is used again
+    /// with the `update` method.
+    pub fn shutdown_guard(&self) -> ShutdownGuard {
+        ShutdownGuard { handle: self.clone() }
+    }
+}
+
+/// A guard that flushes the logger and stops its background thread when it is dropped.
+///
+/// The guard is created with the `shutdown_guard` method of the handle.
+#[derive(Debug)]
+#[must_use = "the logger is shut down when the guard is dropped"]
+pub struct ShutdownGuard {
+    handle: LoggerHandle,
+}
+
+impl Drop for ShutdownGuard {
+    fn drop(&mut self) {
+        log::Log::flush(&*self.handle.logger.read().unwrap_or_else(|e| e.into_inner()));
+        let mut logger = self.handle.logger.write().unwrap_or_else(|e| e.into_inner());
+        logger.asynchronous = None;
+        if let Some(b) = logger.background.take() {
+            b.stop();
         }
     }
+}
+
+/// The logger installed for the `log` crate, which shares its configuration with the handles.
+struct InstalledLogger(Arc<RwLock<Logger>>);
+
+impl log::Log for InstalledLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.0.read().unwrap_or_else(|e| e.into_inner()).enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        self.0.read().unwrap_or_else(|e| e.into_inner()).log(record)
+    }
+
     fn flush(&self) {
-        // println! flushes by itself
+        self.0.read().unwrap_or_else(|e| e.into_inner()).flush()
     }
 }
 
@@ -868,7 +2538,7 @@ impl Default for Logger {
 ///
 /// See the main loggerv documentation page for an example.
 pub fn init_with_level(level: log::Level) -> Result<(), SetLoggerError> {
-    Logger::new().max_level(level).init()
+    Logger::new().max_level(level).init().map(|_| ())
 }
 
 /// Initialize loggerv with a verbosity level.
@@ -876,7 +2546,7 @@ pub fn init_with_level(level: log::Level) -> Result<(), SetLoggerError> {
 /// Intended to be used with an arg parser counting the amount of -v flags.
 /// See the main loggerv documentation page for an example.
 pub fn init_with_verbosity(verbosity: u64) -> Result<(), SetLoggerError> {
-    Logger::new().verbosity(verbosity).init()
+    Logger::new().verbosity(verbosity).init().map(|_| ())
 }
 
 /// Initializes loggerv with only warnings and errors.
@@ -888,6 +2558,7 @@ pub fn init_quiet() -> Result<(), SetLoggerError> {
 
 #[cfg(test)]
 mod tests {
+    use std::thread;
     use log;
     use ansi_term::Colour;
     use super::*;
@@ -898,20 +2569,71 @@ mod tests {
         assert_eq!(logger.include_level, DEFAULT_INCLUDE_LEVEL);
         assert_eq!(logger.include_line_numbers, DEFAULT_INCLUDE_LINE_NUMBERS);
         assert_eq!(logger.include_module_path, DEFAULT_INCLUDE_MODULE_PATH);
-        assert_eq!(logger.colors, DEFAULT_COLORS);
+        assert_eq!(logger.include_thread_name, DEFAULT_INCLUDE_THREAD_NAME);
+        assert!(!logger.thread_colors);
+        assert_eq!(logger.color_choice, DEFAULT_COLOR_CHOICE);
         assert_eq!(logger.level, DEFAULT_LEVEL);
         assert_eq!(logger.separator, String::from(DEFAULT_SEPARATOR));
-        assert_eq!(logger.error.color, DEFAULT_ERROR_COLOR);
-        assert_eq!(logger.warn.color, DEFAULT_WARN_COLOR);
-        assert_eq!(logger.info.color, DEFAULT_INFO_COLOR);
-        assert_eq!(logger.debug.color, DEFAULT_DEBUG_COLOR);
-        assert_eq!(logger.trace.color, DEFAULT_TRACE_COLOR);
+        assert_eq!(logger.format, DEFAULT_FORMAT);
+        assert_eq!(logger.timestamp, DEFAULT_TIMESTAMP);
+        assert_eq!(logger.key_values, DEFAULT_KEY_VALUES);
+        assert_eq!(logger.multi_line, DEFAULT_MULTI_LINE);
+        assert_eq!(logger.overflow, DEFAULT_OVERFLOW);
+        assert!(logger.rate_limit.is_none());
+        assert!(logger.repeats.is_none());
+        assert!(logger.asynchronous.is_none());
+        assert!(logger.background.is_none());
+        assert_eq!(logger.error.style, DEFAULT_ERROR_COLOR.normal());
+        assert_eq!(logger.warn.style, DEFAULT_WARN_COLOR.normal());
+        assert_eq!(logger.info.style, DEFAULT_INFO_COLOR.normal());
+        assert_eq!(logger.debug.style, DEFAULT_DEBUG_COLOR.normal());
+        assert_eq!(logger.trace.style, DEFAULT_TRACE_COLOR.normal());
     }
 
     #[test]
     fn color_works() {
         let logger = Logger::new().color(&log::Level::Trace, Colour::Fixed(11));
-        assert_eq!(logger.trace.color, Colour::Fixed(11));
+        assert_eq!(logger.trace.style, Colour::Fixed(11).normal());
+    }
+
+    #[test]
+    fn style_works() {
+        let logger = Logger::new().style(&log::Level::Error, Colour::Red.bold());
+        assert_eq!(logger.error.style, Colour::Red.bold());
+    }
+
+    #[test]
+    fn component_style_works() {
+        let logger = Logger::new()
+            .no_colors()
+            .level(true)
+            .line_numbers(true)
+            .style(&log::Level::Error, Colour::Red.bold())
+            .component_style(&log::Level::Error, Component::ModulePath, Style::new().dimmed())
+            .component_style(&log::Level::Error, Component::Message, Style::new().italic())
+            .component_style(&log::Level::Error, Component::Message, Style::new().dimmed());
+        let error = log::Level::Error;
+        assert_eq!(logger.select_component_style(&error, Component::Level), Colour::Red.bold());
+        assert_eq!(logger.select_component_style(&error, Component::Separator), Style::new());
+        let message_style = logger.select_component_style(&error, Component::Message);
+        assert_eq!(message_style, Style::new().dimmed());
+        let record = log::Record::builder()
+            .level(log::Level::Error)
+            .module_path(Some("app"))
+            .line(Some(7))
+            .args(format_args!("failed"))
+            .build();
+        assert_eq!(logger.create_line(&record, &Output::Stderr), "ERROR [app] (line 7): failed");
+        assert_eq!(
+            logger.force_colors().create_line(&record, &Output::Stderr),
+            format!(
+                "{} {} {}: {}",
+                Colour::Red.bold().paint("ERROR"),
+                Style::new().dimmed().paint("[app]"),
+                Colour::Red.bold().paint("(line 7)"),
+                Style::new().dimmed().paint("failed")
+            )
+        );
     }
 
     #[test]
@@ -921,16 +2643,73 @@ mod tests {
         assert_eq!(logger.separator, EXPECTED);
     }
 
+    #[test]
+    fn format_works() {
+        let logger = Logger::new().format(Format::Json).piped_format(Format::Text);
+        assert_eq!(logger.format, Format::Json);
+        assert_eq!(logger.piped_format, Some(Format::Text));
+    }
+
+    #[test]
+    fn template_works() {
+        let logger = Logger::new().template("{level} {msg}").unwrap();
+        assert!(logger.template.is_some());
+        assert_eq!(
+            Logger::new().template("{level"),
+            Err(TemplateError::UnclosedField(String::from("level")))
+        );
+    }
+
     #[test]
     fn colors_works() {
         let logger = Logger::new().colors(false);
-        assert!(!logger.colors);
+        assert_eq!(logger.color_choice, ColorChoice::Never);
+        let logger = logger.colors(true);
+        assert_eq!(logger.color_choice, ColorChoice::Auto);
+    }
+
+    #[test]
+    fn force_colors_works() {
+        let logger = Logger::new().force_colors();
+        assert_eq!(logger.color_choice, ColorChoice::Always);
+        assert!(logger.use_colors(&Output::Stderr));
+        assert!(!logger.use_colors(&Output::writer(Vec::new())));
+    }
+
+    #[test]
+    fn use_colors_works() {
+        let mut logger = Logger::new().colors(true);
+        for o in &[Output::Stderr, Output::Stdout] {
+            let expected = env_colors(|v| env::var_os(v)).unwrap_or_else(|| is_terminal(o));
+            assert_eq!(logger.use_colors(o), expected);
+        }
+        logger.env_colors = Some(true);
+        assert!(logger.use_colors(&Output::Stdout));
+        logger.env_colors = Some(false);
+        assert!(!logger.use_colors(&Output::Stderr));
+        assert!(!logger.use_colors(&Output::writer(Vec::new())));
+        assert!(!logger.use_colors(&testing::capture().output()));
+    }
+
+    #[test]
+    fn env_colors_works() {
+        fn env(vars: &'static [(&str, &str)]) -> impl Fn(&str) -> Option<std::ffi::OsString> {
+            move |v| vars.iter().find(|&&(k, _)| k == v).map(|&(_, value)| value.into())
+        }
+        assert_eq!(env_colors(env(&[])), None);
+        assert_eq!(env_colors(env(&[("NO_COLOR", "1"), ("CLICOLOR_FORCE", "1")])), Some(false));
+        assert_eq!(env_colors(env(&[("NO_COLOR", ""), ("CLICOLOR_FORCE", "1")])), Some(true));
+        assert_eq!(env_colors(env(&[("CLICOLOR_FORCE", "0"), ("CLICOLOR", "1")])), None);
+        assert_eq!(env_colors(env(&[("CLICOLOR_FORCE", "1"), ("TERM", "dumb")])), Some(true));
+        assert_eq!(env_colors(env(&[("CLICOLOR", "0")])), Some(false));
+        assert_eq!(env_colors(env(&[("TERM", "dumb")])), Some(false));
+        assert_eq!(env_colors(env(&[("TERM", "xterm-256color")])), None);
     }
 
     #[test]
     fn no_colors_works() {
         let logger = Logger::new().no_colors();
-        assert!(!logger.colors);
+        assert_eq!(logger.color_choice, ColorChoice::Never);
     }
 
     #[test]
@@ -945,6 +2724,52 @@ mod tests {
         assert!(logger.include_level);
     }
 
+    #[test]
+    fn timestamp_works() {
+        let logger = Logger::new().timestamp(Timestamp::Rfc3339Utc);
+        assert_eq!(logger.timestamp, Timestamp::Rfc3339Utc);
+    }
+
+    #[test]
+    fn create_tag_with_timestamp_works() {
+        let logger = Logger::new().no_colors().timestamp(Timestamp::SincePrevious);
+        let record = log::Record::builder().module_path(Some("mycrate")).build();
+        assert!(logger.create_tag(&record, false).starts_with("+0.0"));
+        assert!(logger.create_tag(&record, false).ends_with("s mycrate"));
+        let logger = logger.timestamp(Timestamp::Time).module_path(false);
+        assert_eq!(logger.create_tag(&record, false).len(), "00:00:00.000".len());
+    }
+
+    #[test]
+    fn create_tag_with_thread_name_works() {
+        let logger = Logger::new().no_colors().level(true).thread_name(true);
+        let tag = thread::Builder::new()
+            .name(String::from("worker-1"))
+            .spawn(move || {
+                let record = log::Record::builder()
+                    .level(log::Level::Warn)
+                    .module_path(Some("mycrate"))
+                    .build();
+                logger.create_tag(&record, false)
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+        assert_eq!(tag, "WARN [mycrate] <worker-1>");
+    }
+
+    #[test]
+    fn create_tag_with_thread_colors_works() {
+        let logger = Logger::new().module_path(false).thread_name(true).thread_colors(true);
+        let record = log::Record::builder().build();
+        let name = format::thread_name();
+        assert_eq!(
+            logger.create_tag(&record, true),
+            thread_color(&name).paint(format!("<{}>", name)).to_string()
+        );
+        assert_eq!(thread_color("worker-1"), thread_color("worker-1"));
+    }
+
     #[test]
     fn max_level_works() {
         let logger = Logger::new().max_level(log::Level::Trace);
@@ -991,6 +2816,359 @@ mod tests {
         assert_eq!(logger.trace.output, Output::Stderr);
     }
 
+    #[test]
+    fn module_level_works() {
+        let logger = Logger::new().module_level("hyper", log::LevelFilter::Warn);
+        assert_eq!(logger.module_levels, vec![(String::from("hyper"), log::LevelFilter::Warn)]);
+        let logger = Logger::new()
+            .max_level(log::Level::Info)
+            .module_level("audit", log::LevelFilter::Debug);
+        let metadata = |target| {
+            log::Metadata::builder().level(log::Level::Debug).target(target).build()
+        };
+        assert!(log::Log::enabled(&logger, &metadata("audit")));
+        assert!(!log::Log::enabled(&logger, &metadata("mycrate")));
+    }
+
+    #[test]
+    fn select_level_works() {
+        let logger = Logger::new()
+            .max_level(log::Level::Info)
+            .module_level("mycrate", log::LevelFilter::Trace)
+            .module_level("hyper", log::LevelFilter::Warn)
+            .module_level("mycrate::db", log::LevelFilter::Debug);
+        assert_eq!(logger.select_level("mycrate"), log::LevelFilter::Trace);
+        assert_eq!(logger.select_level("mycrate::net"), log::LevelFilter::Trace);
+        assert_eq!(logger.select_level("mycrate::db::pool"), log::LevelFilter::Debug);
+        assert_eq!(logger.select_level("mycrate_utils"), log::LevelFilter::Info);
+        assert_eq!(logger.select_level("hyper::client"), log::LevelFilter::Warn);
+        assert_eq!(logger.select_level("other"), log::LevelFilter::Info);
+        assert_eq!(logger.max_level_filter(), log::LevelFilter::Trace);
+    }
+
+    #[test]
+    fn directives_works() {
+        let logger = Logger::new()
+            .verbosity(0)
+            .module_level("hyper", log::LevelFilter::Trace)
+            .directives("info, hyper=warn,mycrate::db=DEBUG,mycrate")
+            .unwrap()
+            .module_level("hyper", log::LevelFilter::Error);
+        assert_eq!(logger.level_override, Some(log::LevelFilter::Info));
+        assert_eq!(logger.select_level("other"), log::LevelFilter::Info);
+        assert_eq!(logger.select_level("hyper"), log::LevelFilter::Warn);
+        assert_eq!(logger.select_level("mycrate::db"), log::LevelFilter::Debug);
+        assert_eq!(logger.select_level("mycrate::net"), log::LevelFilter::Trace);
+    }
+
+    #[test]
+    fn directives_errors_work() {
+        assert_eq!(
+            Logger::new().directives("hyper=loud"),
+            Err(DirectiveError::InvalidLevel(String::from("hyper=loud")))
+        );
+        assert_eq!(
+            Logger::new().directives("info,=debug"),
+            Err(DirectiveError::EmptyModulePath(String::from("=debug")))
+        );
+    }
+
+    #[test]
+    fn env_override_works() {
+        env::set_var("LOGGERV_TEST_ENV_OVERRIDE", "off,mycrate=debug");
+        let logger = Logger::from_env("LOGGERV_TEST_ENV_OVERRIDE").unwrap();
+        assert_eq!(logger.select_level("other"), log::LevelFilter::Off);
+        assert_eq!(logger.select_level("mycrate"), log::LevelFilter::Debug);
+        let logger = Logger::new().env_override("LOGGERV_TEST_ENV_UNSET").unwrap();
+        assert_eq!(logger, Logger::new());
+    }
+
+    /// A writer to a buffer that can be inspected after it has been moved into an output.
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn writer_output_works() {
+        let buffer = Buffer::default();
+        let output = Output::writer(buffer.clone());
+        assert_eq!(output, output.clone());
+        assert_ne!(output, Output::writer(Buffer::default()));
+        let logger = Logger::new()
+            .no_colors()
+            .output(&log::Level::Error, output);
+        log::Log::log(&logger, &log::Record::builder()
+            .level(log::Level::Error)
+            .module_path(Some("mycrate"))
+            .args(format_args!("written"))
+            .build());
+        assert_eq!(String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap(), "mycrate: written\n");
+    }
+
+    /// A writer that always fails, like a file on a full disk.
+    struct Full;
+
+    impl Write for Full {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::Other, "disk full"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Err(io::Error::new(io::ErrorKind::Other, "disk full"))
+        }
+    }
+
+    #[test]
+    fn writer_output_failure_works() {
+        let output = Output::writer(Full);
+        let logger = Logger::new().output(&log::Level::Error, output.clone());
+        log_to(&logger, log::Level::Error, "mycrate", "not written");
+        log_to(&logger, log::Level::Error, "mycrate", "not written");
+        log::Log::flush(&logger);
+        match output {
+            Output::Writer(ref w) => assert!(!w.first_failure()),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn file_output_works() {
+        let path = env::temp_dir().join(format!("loggerv-file-output-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let logger = Logger::new()
+            .no_colors()
+            .output(&log::Level::Warn, Output::file(&path).unwrap());
+        log::Log::log(&logger, &log::Record::builder()
+            .level(log::Level::Warn)
+            .module_path(Some("mycrate"))
+            .args(format_args!("appended"))
+            .build());
+        log::Log::flush(&logger);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "mycrate: appended\n");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn syslog_output_failure_works() {
+        use std::net::UdpSocket;
+        use std::os::unix::net::UnixDatagram;
+
+        let closed = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let udp = Syslog::udp(closed.local_addr().unwrap()).unwrap();
+        drop(closed);
+        let path = env::temp_dir()
+            .join(format!("loggerv-syslog-failure-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let server = UnixDatagram::bind(&path).unwrap();
+        let unix = Syslog::unix_path(&path).unwrap();
+        drop(server);
+        std::fs::remove_file(&path).unwrap();
+        for syslog in [udp, unix.clone()] {
+            let logger = Logger::new().output(&log::Level::Error, Output::Syslog(syslog));
+            for _ in 0..3 {
+                log_to(&logger, log::Level::Error, "mycrate", "not delivered");
+            }
+        }
+        assert!(!unix.first_failure());
+    }
+
+    fn log_to(logger: &Logger, level: log::Level, module_path: &str, message: &str) {
+        log::Log::log(logger, &log::Record::builder()
+            .level(level)
+            .target(module_path)
+            .module_path(Some(module_path))
+            .line(Some(7))
+            .args(format_args!("{}", message))
+            .build());
+    }
+
+    #[test]
+    fn log_works() {
+        let capture = testing::capture();
+        let logger = capture.logger().max_level(log::Level::Info);
+        log_to(&logger, log::Level::Error, "app", "failed");
+        log_to(&logger, log::Level::Debug, "app", "hidden");
+        let logger = logger.level(true).line_numbers(true).separator(" = ");
+        log_to(&logger, log::Level::Warn, "app::db", "slow");
+        let logger = logger.level(false).line_numbers(false).no_module_path();
+        log_to(&logger, log::Level::Info, "app", "bare");
+        assert_eq!(capture.lines(), vec![
+            "app: failed",
+            "WARN [app::db] (line 7) = slow",
+            "bare",
+        ]);
+    }
+
+    #[test]
+    fn log_key_values_works() {
+        let capture = testing::capture();
+        let logger = capture.logger();
+        let kvs: &[(&str, &dyn log::kv::ToValue)] = &[("user", &42), ("name", &"ann lee")];
+        let record = log::Record::builder()
+            .level(log::Level::Info)
+            .module_path(Some("app"))
+            .args(format_args!("login"))
+            .key_values(&kvs)
+            .build();
+        log::Log::log(&logger, &record);
+        log::Log::log(&logger.clone().key_values(KeyValues::InTag), &record);
+        log::Log::log(&logger.clone().key_values(KeyValues::InTag).no_module_path(), &record);
+        log::Log::log(&logger.key_values(KeyValues::Off), &record);
+        assert_eq!(capture.lines(), vec![
+            r#"app: login user=42 name="ann lee""#,
+            r#"app user=42 name="ann lee": login"#,
+            r#"user=42 name="ann lee": login"#,
+            "app: login",
+        ]);
+    }
+
+    #[test]
+    fn log_filters_work() {
+        let capture = testing::capture();
+        let logger = capture.logger()
+            .max_level(log::Level::Info)
+            .module_level("hyper", log::LevelFilter::Warn)
+            .add_module_path_filter("app")
+            .add_module_path_filter("hyper");
+        log_to(&logger, log::Level::Info, "app", "shown");
+        log_to(&logger, log::Level::Info, "hyper::client", "hidden by level");
+        log_to(&logger, log::Level::Warn, "hyper::client", "shown");
+        log_to(&logger, log::Level::Error, "tokio", "hidden by filter");
+        assert_eq!(capture.lines(), vec!["app: shown", "hyper::client: shown"]);
+    }
+
+    #[test]
+    fn filter_module_path_works() {
+        let logger = Logger::new();
+        assert!(logger.filter_module_path("anything"));
+        let logger = Logger::new().add_module_path_filter("!tokio::").add_module_path_filter("-h2");
+        assert!(logger.filter_module_path("app"));
+        assert!(logger.filter_module_path("h2o"));
+        assert!(!logger.filter_module_path("tokio"));
+        assert!(!logger.filter_module_path("tokio::net"));
+        assert!(!logger.filter_module_path("h2::frame"));
+        let logger = logger
+            .add_module_path_filter("foo")
+            .add_module_path_filter("tokio::net")
+            .add_module_path_filter("bar*");
+        assert!(logger.filter_module_path("foo::db"));
+        assert!(!logger.filter_module_path("foobar"));
+        assert!(logger.filter_module_path("barbaz"));
+        assert!(logger.filter_module_path("tokio::net::tcp"));
+        assert!(!logger.filter_module_path("tokio::io"));
+        assert!(!logger.filter_module_path("app"));
+        let logger = Logger::new().add_module_path_filter("foo").add_module_path_filter("!foo");
+        assert!(!logger.filter_module_path("foo"));
+    }
+
+    #[test]
+    fn log_message_filters_work() {
+        let capture = testing::capture();
+        let logger = capture.logger()
+            .message_filter(MessageFilter::exclude("harmless").unwrap().target("^hyper").unwrap());
+        log_to(&logger, log::Level::Warn, "hyper::client", "harmless warning");
+        log_to(&logger, log::Level::Error, "hyper::client", "real error");
+        log_to(&logger, log::Level::Warn, "app", "harmless for hyper only");
+        assert_eq!(
+            capture.lines(),
+            vec!["hyper::client: real error", "app: harmless for hyper only"]
+        );
+    }
+
+    #[test]
+    fn log_rate_limit_works() {
+        let capture = testing::capture();
+        let logger = capture.logger().rate_limit(2, 0);
+        for _ in 0..5 {
+            log_to(&logger, log::Level::Warn, "app", "retrying");
+        }
+        log_to(&logger, log::Level::Warn, "app::db", "reconnecting");
+        log::Log::flush(&logger);
+        assert_eq!(capture.lines(), vec![
+            "app: retrying",
+            "app: retrying",
+            "app::db: reconnecting",
+            "app: suppressed 3 similar messages",
+        ]);
+    }
+
+    #[test]
+    fn log_rate_limit_quiet_callsite_works() {
+        let capture = testing::capture();
+        let logger = capture.logger().rate_limit(1, 100);
+        for _ in 0..3 {
+            log_to(&logger, log::Level::Warn, "app", "retrying");
+        }
+        thread::sleep(std::time::Duration::from_millis(20));
+        log_to(&logger, log::Level::Info, "app::db", "reconnected");
+        assert_eq!(capture.lines(), vec![
+            "app: retrying",
+            "app: suppressed 2 similar messages",
+            "app::db: reconnected",
+        ]);
+    }
+
+    #[test]
+    fn log_collapse_repeats_works() {
+        let capture = testing::capture();
+        let logger = capture.logger().collapse_repeats(true);
+        for _ in 0..3 {
+            log_to(&logger, log::Level::Info, "app", "polling");
+        }
+        log_to(&logger, log::Level::Info, "app", "done");
+        log_to(&logger, log::Level::Info, "app", "done");
+        log::Log::flush(&logger);
+        assert_eq!(capture.lines(), vec![
+            "app: polling",
+            "app: last message repeated 2 times",
+            "app: done",
+            "app: last message repeated 1 times",
+        ]);
+    }
+
+    #[test]
+    fn log_asynchronous_works() {
+        let capture = testing::capture();
+        let logger = capture.logger().asynchronous(4, QueueFull::Block);
+        assert!(logger.background.is_none());
+        let handle = LoggerHandle { logger: Arc::new(RwLock::new(logger)) };
+        handle.update(|logger| logger.asynchronous(8, QueueFull::Block));
+        let first = handle.logger.read().unwrap().background.clone().unwrap();
+        assert_eq!(first.settings(), (8, QueueFull::Block));
+        handle.update(|logger| logger.asynchronous(8, QueueFull::Block));
+        assert!(first.send(&capture.output(), &log::Record::builder().build(), "still running"));
+        for i in 0..10 {
+            let message = format!("message {}", i);
+            log_to(&handle.logger.read().unwrap(), log::Level::Info, "app", &message);
+        }
+        handle.update(|logger| logger.asynchronous(4, QueueFull::DropNewest));
+        assert!(!first.send(&capture.output(), &log::Record::builder().build(), "stopped"));
+        log::Log::flush(&*handle.logger.read().unwrap());
+        assert_eq!(capture.lines().len(), 11);
+        assert_eq!(capture.lines()[10], "app: message 9");
+        drop(handle.shutdown_guard());
+        assert!(handle.logger.read().unwrap().background.is_none());
+        log_to(&handle.logger.read().unwrap(), log::Level::Info, "app", "after shutdown");
+        assert_eq!(capture.lines()[11], "app: after shutdown");
+        handle.update(|logger| logger.line_numbers(false));
+        assert!(handle.logger.read().unwrap().background.is_none());
+        handle.update(|logger| logger.asynchronous(4, QueueFull::DropNewest));
+        let restarted = handle.logger.read().unwrap().background.clone().unwrap();
+        assert!(restarted.send(&capture.output(), &log::Record::builder().build(), "restarted"));
+        drop(handle.shutdown_guard());
+        assert_eq!(capture.lines()[12], "restarted");
+    }
+
     #[test]
     fn init_works() {
         let result = Logger::new().init();
@@ -998,13 +3176,108 @@ mod tests {
     }
 
     #[test]
-    fn select_color_works() {
+    fn handle_works() {
+        let handle = LoggerHandle { logger: Arc::new(RwLock::new(Logger::new().verbosity(0))) };
+        handle.set_verbosity(2);
+        assert_eq!(handle.logger.read().unwrap().level, log::Level::Debug);
+        handle.set_max_level(log::Level::Error);
+        assert_eq!(handle.logger.read().unwrap().level, log::Level::Error);
+        handle.set_module_level("hyper", log::LevelFilter::Debug);
+        handle.set_module_level("hyper", log::LevelFilter::Warn);
+        assert_eq!(
+            handle.logger.read().unwrap().module_levels,
+            vec![(String::from("hyper"), log::LevelFilter::Warn)]
+        );
+        assert_eq!(handle.logger.read().unwrap().max_level_filter(), log::LevelFilter::Warn);
+        handle.set_colors(false);
+        assert_eq!(handle.logger.read().unwrap().color_choice, ColorChoice::Never);
+        handle.set_output(&log::Level::Info, Output::Stdout);
+        assert_eq!(handle.logger.read().unwrap().info.output, Output::Stdout);
+        let capture = testing::capture();
+        handle.update(|logger| {
+            log::Log::log(&InstalledLogger(handle.logger.clone()), &log::Record::builder()
+                .level(log::Level::Error)
+                .args(format_args!("logged while updating"))
+                .build());
+            logger.output(&log::Level::Error, capture.output())
+        });
+        assert_eq!(handle.logger.read().unwrap().error.output, capture.output());
+
+        let logger = Logger::new().verbosity(0).directives("warn,hyper=error").unwrap();
+        let handle = LoggerHandle { logger: Arc::new(RwLock::new(logger)) };
+        handle.set_max_level(log::Level::Info);
+        assert_eq!(handle.logger.read().unwrap().select_level("other"), log::LevelFilter::Info);
+        handle.set_verbosity(2);
+        assert_eq!(handle.logger.read().unwrap().select_level("other"), log::LevelFilter::Debug);
+        handle.set_module_level("hyper", log::LevelFilter::Trace);
+        assert_eq!(handle.logger.read().unwrap().select_level("hyper"), log::LevelFilter::Trace);
+        assert_eq!(handle.logger.read().unwrap().max_level_filter(), log::LevelFilter::Trace);
+    }
+
+    #[test]
+    fn create_line_without_tag_works() {
+        let logger = Logger::new().no_colors().no_module_path();
+        let record = log::Record::builder().args(format_args!("message")).build();
+        assert_eq!(logger.create_line(&record, &Output::Stderr), "message");
+    }
+
+    #[test]
+    fn multi_line_works() {
+        let logger = Logger::new().no_colors().level(true);
+        let record = log::Record::builder()
+            .level(log::Level::Error)
+            .module_path(Some("app"))
+            .args(format_args!("failed:\n  disk full"))
+            .build();
+        assert_eq!(logger.create_line(&record, &Output::Stderr), "ERROR [app]: failed:\n  disk full");
+        let logger = logger.multi_line(MultiLine::Indent);
+        assert_eq!(
+            logger.create_line(&record, &Output::Stderr),
+            "ERROR [app]: failed:\n               disk full"
+        );
+        let logger = logger.multi_line(MultiLine::RepeatTag);
+        assert_eq!(
+            logger.create_line(&record, &Output::Stderr),
+            "ERROR [app]: failed:\nERROR [app]:   disk full"
+        );
+        let logger = logger.colors(true).multi_line(MultiLine::Indent);
+        assert!(logger.create_line(&record, &Output::Stderr)
+            .ends_with("\n               disk full"));
+    }
+
+    #[test]
+    fn fit_works() {
+        let logger = Logger::new().no_colors().level(true);
+        let record = log::Record::builder()
+            .level(log::Level::Error)
+            .module_path(Some("app"))
+            .args(format_args!("failed to open the configuration file"))
+            .build();
+        let (line, indent) = logger.create_text(&record, false);
+        assert_eq!(indent, "ERROR [app]: ".len());
+        assert_eq!(logger.fit(line.clone(), indent, Some(30)), line);
+        let logger = logger.overflow(Overflow::Wrap);
+        assert_eq!(logger.fit(line.clone(), indent, None), line);
+        assert_eq!(
+            logger.fit(line.clone(), indent, Some(40)),
+            "ERROR [app]: failed to open the\n             configuration file"
+        );
+        assert_eq!(
+            logger.fit(line.clone(), indent, Some(30)),
+            "ERROR [app]: failed to open\n          the configuration\n          file"
+        );
+        let logger = logger.overflow(Overflow::Truncate);
+        assert_eq!(logger.fit(line, indent, Some(30)), "ERROR [app]: failed to open t…");
+    }
+
+    #[test]
+    fn select_style_works() {
         let logger = Logger::new();
-        assert_eq!(logger.select_color(&log::Level::Error), DEFAULT_ERROR_COLOR);
-        assert_eq!(logger.select_color(&log::Level::Warn), DEFAULT_WARN_COLOR);
-        assert_eq!(logger.select_color(&log::Level::Info), DEFAULT_INFO_COLOR);
-        assert_eq!(logger.select_color(&log::Level::Debug), DEFAULT_DEBUG_COLOR);
-        assert_eq!(logger.select_color(&log::Level::Trace), DEFAULT_TRACE_COLOR);
+        assert_eq!(logger.select_style(&log::Level::Error), DEFAULT_ERROR_COLOR.normal());
+        assert_eq!(logger.select_style(&log::Level::Warn), DEFAULT_WARN_COLOR.normal());
+        assert_eq!(logger.select_style(&log::Level::Info), DEFAULT_INFO_COLOR.normal());
+        assert_eq!(logger.select_style(&log::Level::Debug), DEFAULT_DEBUG_COLOR.normal());
+        assert_eq!(logger.select_style(&log::Level::Trace), DEFAULT_TRACE_COLOR.normal());
     }
 }
 
@@ -0,0 +1,184 @@
+//! Rate limiting of the log statements from each callsite.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use log;
+
+/// The place in the source code of a log statement.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Callsite {
+    pub module_path: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+}
+
+impl Callsite {
+    /// Gets the callsite of a log statement.
+    pub fn of(record: &log::Record) -> Callsite {
+        Callsite {
+            module_path: record.module_path().map(String::from),
+            file: record.file().map(String::from),
+            line: record.line(),
+        }
+    }
+}
+
+/// The log statements that were suppressed at a callsite.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suppressed {
+    pub callsite: Callsite,
+    pub level: log::Level,
+    pub target: String,
+    pub count: u64,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    level: log::Level,
+    target: String,
+    suppressed: u64,
+}
+
+/// A token bucket for each callsite, which holds up to the burst and is refilled with the budget
+/// every second.
+///
+/// Clones of the rate limiter share the buckets. Two rate limiters are equal if they have the
+/// same burst and budget.
+#[derive(Clone)]
+pub struct RateLimiter {
+    burst: u32,
+    per_second: u32,
+    buckets: Arc<Mutex<HashMap<Callsite, Bucket>>>,
+}
+
+impl RateLimiter {
+    pub fn new(burst: u32, per_second: u32) -> RateLimiter {
+        RateLimiter {
+            burst,
+            per_second,
+            buckets: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Takes a token for a log statement from the bucket of its callsite.
+    ///
+    /// Returns `None` if the log statement is suppressed, or the number of log statements that
+    /// were suppressed at the callsite since the last one that was logged.
+    pub fn check(&self, record: &log::Record, now: Instant) -> Option<u64> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let bucket = buckets.entry(Callsite::of(record)).or_insert_with(|| Bucket {
+            tokens: f64::from(self.burst),
+            updated: now,
+            level: record.level(),
+            target: record.target().to_string(),
+            suppressed: 0,
+        });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens =
+            (bucket.tokens + elapsed * f64::from(self.per_second)).min(f64::from(self.burst));
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            let suppressed = bucket.suppressed;
+            bucket.suppressed = 0;
+            Some(suppressed)
+        } else {
+            bucket.level = record.level();
+            bucket.suppressed += 1;
+            None
+        }
+    }
+
+    /// Takes the counts of the suppressed log statements of all of the callsites.
+    pub fn take_suppressed(&self) -> Vec<Suppressed> {
+        self.take(|_| true)
+    }
+
+    /// Takes the counts of the suppressed log statements of the callsites whose buckets have
+    /// been refilled with a token since, which means their window has reset.
+    pub fn take_expired(&self, now: Instant) -> Vec<Suppressed> {
+        let per_second = f64::from(self.per_second);
+        self.take(|bucket| {
+            let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+            bucket.tokens + elapsed * per_second >= 1.0
+        })
+    }
+
+    fn take<F: Fn(&Bucket) -> bool>(&self, expired: F) -> Vec<Suppressed> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let mut suppressed = Vec::new();
+        let pending = buckets.iter_mut().filter(|(_, b)| b.suppressed > 0 && expired(b));
+        for (callsite, bucket) in pending {
+            suppressed.push(Suppressed {
+                callsite: callsite.clone(),
+                level: bucket.level,
+                target: bucket.target.clone(),
+                count: bucket.suppressed,
+            });
+            bucket.suppressed = 0;
+        }
+        suppressed
+    }
+}
+
+impl fmt::Debug for RateLimiter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RateLimiter")
+            .field("burst", &self.burst)
+            .field("per_second", &self.per_second)
+            .finish()
+    }
+}
+
+impl PartialEq for RateLimiter {
+    fn eq(&self, other: &RateLimiter) -> bool {
+        self.burst == other.burst && self.per_second == other.per_second
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use super::*;
+
+    #[test]
+    fn check_works() {
+        let limiter = RateLimiter::new(2, 1);
+        let retry = log::Record::builder().level(log::Level::Warn).line(Some(10)).build();
+        let other = log::Record::builder().level(log::Level::Warn).line(Some(20)).build();
+        let start = Instant::now();
+        assert_eq!(limiter.check(&retry, start), Some(0));
+        assert_eq!(limiter.check(&retry, start), Some(0));
+        assert_eq!(limiter.check(&retry, start), None);
+        assert_eq!(limiter.check(&retry, start + Duration::from_millis(500)), None);
+        assert_eq!(limiter.check(&other, start), Some(0));
+        assert_eq!(limiter.check(&retry, start + Duration::from_secs(1)), Some(2));
+        assert_eq!(limiter.check(&retry, start + Duration::from_secs(1)), None);
+        let suppressed = limiter.take_suppressed();
+        assert_eq!(suppressed.len(), 1);
+        assert_eq!(suppressed[0].callsite.line, Some(10));
+        assert_eq!(suppressed[0].count, 1);
+        assert!(limiter.take_suppressed().is_empty());
+    }
+
+    #[test]
+    fn take_expired_works() {
+        let limiter = RateLimiter::new(1, 1);
+        let retry = log::Record::builder().level(log::Level::Warn).line(Some(10)).build();
+        let start = Instant::now();
+        assert_eq!(limiter.check(&retry, start), Some(0));
+        assert_eq!(limiter.check(&retry, start), None);
+        assert_eq!(limiter.check(&retry, start), None);
+        assert!(limiter.take_expired(start + Duration::from_millis(500)).is_empty());
+        let expired = limiter.take_expired(start + Duration::from_secs(1));
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].count, 2);
+        assert!(limiter.take_expired(start + Duration::from_secs(2)).is_empty());
+        assert_eq!(limiter.check(&retry, start + Duration::from_secs(2)), Some(0));
+    }
+}
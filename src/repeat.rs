@@ -0,0 +1,130 @@
+//! Collapsing consecutive log statements with the same message.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use log;
+
+use ratelimit::Callsite;
+
+/// A log statement that was repeated after it was logged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repeated {
+    pub callsite: Callsite,
+    pub level: log::Level,
+    pub target: String,
+    pub count: u64,
+}
+
+#[derive(Debug)]
+struct Last {
+    repeated: Repeated,
+    message: String,
+}
+
+/// How a log statement compares to the previous one.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Seen {
+    /// The log statement has the same level, module path, and message as the previous one.
+    Repeat,
+    /// The log statement is different from the previous one, which might have been repeated.
+    New(Option<Repeated>),
+}
+
+/// The last log statement and the number of times it was repeated.
+///
+/// Clones share the last log statement. All of them are equal, since the last log statement is
+/// state of a running logger and not part of its configuration.
+#[derive(Clone, Default)]
+pub struct Repeats {
+    last: Arc<Mutex<Option<Last>>>,
+}
+
+impl Repeats {
+    /// Compares a log statement to the previous one and remembers it.
+    pub fn check(&self, record: &log::Record) -> Seen {
+        let message = record.args().to_string();
+        let mut last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(ref mut l) = *last {
+            if l.repeated.level == record.level()
+                && l.repeated.callsite.module_path.as_deref() == record.module_path()
+                && l.message == message
+            {
+                l.repeated.count += 1;
+                return Seen::Repeat;
+            }
+        }
+        let previous = last.take().map(|l| l.repeated).filter(|r| r.count > 0);
+        *last = Some(Last {
+            repeated: Repeated {
+                callsite: Callsite::of(record),
+                level: record.level(),
+                target: record.target().to_string(),
+                count: 0,
+            },
+            message,
+        });
+        Seen::New(previous)
+    }
+
+    /// Takes the number of times the last log statement was repeated, if it was repeated.
+    pub fn take(&self) -> Option<Repeated> {
+        let mut last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+        match *last {
+            Some(ref mut l) if l.repeated.count > 0 => {
+                let repeated = l.repeated.clone();
+                l.repeated.count = 0;
+                Some(repeated)
+            },
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Debug for Repeats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Repeats")
+    }
+}
+
+impl PartialEq for Repeats {
+    fn eq(&self, _: &Repeats) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(repeats: &Repeats, level: log::Level, message: &str) -> Seen {
+        repeats.check(&log::Record::builder()
+            .level(level)
+            .module_path(Some("app"))
+            .args(format_args!("{}", message))
+            .build())
+    }
+
+    #[test]
+    fn check_works() {
+        let repeats = Repeats::default();
+        assert_eq!(check(&repeats, log::Level::Info, "polling"), Seen::New(None));
+        assert_eq!(check(&repeats, log::Level::Info, "polling"), Seen::Repeat);
+        assert_eq!(check(&repeats, log::Level::Info, "polling"), Seen::Repeat);
+        match check(&repeats, log::Level::Warn, "polling") {
+            Seen::New(Some(r)) => {
+                assert_eq!(r.level, log::Level::Info);
+                assert_eq!(r.count, 2);
+            },
+            seen => panic!("unexpected {:?}", seen),
+        }
+        assert_eq!(check(&repeats, log::Level::Warn, "polling"), Seen::Repeat);
+        assert_eq!(repeats.take().map(|r| r.count), Some(1));
+        assert_eq!(repeats.take(), None);
+        assert_eq!(check(&repeats, log::Level::Warn, "polling"), Seen::Repeat);
+        match check(&repeats, log::Level::Warn, "done") {
+            Seen::New(Some(r)) => assert_eq!(r.count, 1),
+            seen => panic!("unexpected {:?}", seen),
+        }
+    }
+}
@@ -0,0 +1,316 @@
+//! Log files that are rotated by size or time.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+
+#[cfg(feature = "gzip")]
+use flate2::write::GzEncoder;
+#[cfg(feature = "gzip")]
+use flate2::Compression;
+
+/// How often a log file is rotated based on the local time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    /// Rotates at the start of every hour.
+    Hourly,
+    /// Rotates at midnight.
+    Daily,
+}
+
+impl Period {
+    fn key(self, t: DateTime<Local>) -> String {
+        match self {
+            Period::Hourly => t.format("%Y%m%d%H").to_string(),
+            Period::Daily => t.format("%Y%m%d").to_string(),
+        }
+    }
+}
+
+/// The rotation policy for a log file.
+///
+/// When a log file is rotated, it is renamed with a `.1` suffix, the previous `.1` file is
+/// renamed with a `.2` suffix, and so on, and the oldest file is removed once there are more than
+/// the number of files to keep.
+///
+/// # Example
+///
+/// ```rust
+/// #[macro_use] extern crate log;
+/// extern crate loggerv;
+///
+/// use log::Level;
+/// use loggerv::{Output, Period, Rotation};
+///
+/// fn main() {
+///     let file = Rotation::new(std::env::temp_dir().join("loggerv-rotation.log"))
+///         .max_size(10 * 1024 * 1024)
+///         .period(Period::Daily)
+///         .keep(7)
+///         .open()
+///         .unwrap();
+///     loggerv::Logger::new()
+///         .output(&Level::Error, Output::writer(file))
+///         .init()
+///         .unwrap();
+///
+///     error!("This is written to the rotated file");
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rotation {
+    path: PathBuf,
+    max_size: Option<u64>,
+    period: Option<Period>,
+    keep: usize,
+    #[cfg(feature = "gzip")]
+    compress: bool,
+}
+
+impl Rotation {
+    /// Creates a new rotation policy for a log file.
+    ///
+    /// The default is to never rotate the file and to keep 5 rotated files once it is rotated.
+    pub fn new<P: AsRef<Path>>(path: P) -> Rotation {
+        Rotation {
+            path: path.as_ref().to_path_buf(),
+            max_size: None,
+            period: None,
+            keep: 5,
+            #[cfg(feature = "gzip")]
+            compress: false,
+        }
+    }
+
+    /// Rotates the file before a log statement would make it larger than a number of bytes.
+    ///
+    /// A log statement larger than the maximum size is still written to an empty file.
+    pub fn max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// Rotates the file when the hour or day of the local time changes.
+    pub fn period(mut self, p: Period) -> Self {
+        self.period = Some(p);
+        self
+    }
+
+    /// Sets the number of rotated files to keep.
+    ///
+    /// With zero (0), the file is truncated instead of rotated.
+    pub fn keep(mut self, k: usize) -> Self {
+        self.keep = k;
+        self
+    }
+
+    /// Enables or disables compressing rotated files with gzip, which adds a `.gz` suffix.
+    ///
+    /// The file is compressed while it is rotated, so the log statement that triggered the
+    /// rotation waits for the compression.
+    #[cfg(feature = "gzip")]
+    pub fn compress(mut self, c: bool) -> Self {
+        self.compress = c;
+        self
+    }
+
+    /// Opens the log file for appending, creating it if it does not exist.
+    pub fn open(self) -> io::Result<RotatingFile> {
+        let file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        let metadata = file.metadata()?;
+        let period = self.period.map(|p| {
+            let modified = metadata.modified().map(DateTime::from).unwrap_or_else(|_| Local::now());
+            p.key(modified)
+        });
+        Ok(RotatingFile {
+            size: metadata.len(),
+            file,
+            period,
+            rotation: self,
+        })
+    }
+}
+
+/// A log file that is rotated based on a rotation policy.
+///
+/// Use the `Output::writer` function to write log statements to it. If the file cannot be
+/// rotated, such as when a rotated file cannot be renamed, the write fails, and the logger writes
+/// the log statement to `stderr` instead. The rotation is tried again with the next log statement.
+#[derive(Debug)]
+pub struct RotatingFile {
+    rotation: Rotation,
+    file: File,
+    size: u64,
+    period: Option<String>,
+}
+
+impl RotatingFile {
+    fn needs_rotation(&self, len: usize) -> bool {
+        let too_big = self.rotation.max_size
+            .is_some_and(|m| self.size > 0 && self.size + len as u64 > m);
+        let new_period = match (self.rotation.period, &self.period) {
+            (Some(p), Some(k)) => p.key(Local::now()) != *k,
+            _ => false,
+        };
+        too_big || new_period
+    }
+
+    fn rotated_path(&self, n: usize) -> PathBuf {
+        let mut name = self.rotation.path.clone().into_os_string();
+        name.push(format!(".{}", n));
+        #[cfg(feature = "gzip")]
+        {
+            if self.rotation.compress {
+                name.push(".gz");
+            }
+        }
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        let keep = self.rotation.keep;
+        if keep == 0 {
+            self.file = File::create(&self.rotation.path)?;
+        } else {
+            let oldest = self.rotated_path(keep);
+            if oldest.exists() {
+                fs::remove_file(&oldest)?;
+            }
+            for n in (1..keep).rev() {
+                let from = self.rotated_path(n);
+                if from.exists() {
+                    fs::rename(&from, self.rotated_path(n + 1))?;
+                }
+            }
+            self.archive()?;
+            self.file = OpenOptions::new().create(true).append(true).open(&self.rotation.path)?;
+        }
+        self.size = 0;
+        self.period = self.rotation.period.map(|p| p.key(Local::now()));
+        Ok(())
+    }
+
+    #[cfg(not(feature = "gzip"))]
+    fn archive(&self) -> io::Result<()> {
+        fs::rename(&self.rotation.path, self.rotated_path(1))
+    }
+
+    #[cfg(feature = "gzip")]
+    fn archive(&self) -> io::Result<()> {
+        if !self.rotation.compress {
+            return fs::rename(&self.rotation.path, self.rotated_path(1));
+        }
+        let mut encoder = GzEncoder::new(File::create(self.rotated_path(1))?, Compression::default());
+        io::copy(&mut File::open(&self.rotation.path)?, &mut encoder)?;
+        encoder.finish()?;
+        fs::remove_file(&self.rotation.path)
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.needs_rotation(buf.len()) {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::process;
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("loggerv-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn max_size_works() {
+        let dir = temp_dir("max-size");
+        let path = dir.join("app.log");
+        let mut file = Rotation::new(&path).max_size(8).keep(2).open().unwrap();
+        for line in &["first\n", "second\n", "third\n", "fourth\n"] {
+            file.write_all(line.as_bytes()).unwrap();
+        }
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fourth\n");
+        assert_eq!(fs::read_to_string(dir.join("app.log.1")).unwrap(), "third\n");
+        assert_eq!(fs::read_to_string(dir.join("app.log.2")).unwrap(), "second\n");
+        assert!(!dir.join("app.log.3").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn keep_zero_works() {
+        let dir = temp_dir("keep-zero");
+        let path = dir.join("app.log");
+        let mut file = Rotation::new(&path).max_size(8).keep(0).open().unwrap();
+        file.write_all(b"first\n").unwrap();
+        file.write_all(b"second\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second\n");
+        assert!(!dir.join("app.log.1").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rotation_failure_works() {
+        let dir = temp_dir("failure");
+        let path = dir.join("app.log");
+        fs::create_dir(dir.join("app.log.1")).unwrap();
+        let mut file = Rotation::new(&path).max_size(8).keep(1).open().unwrap();
+        file.write_all(b"first\n").unwrap();
+        assert!(file.write_all(b"second\n").is_err());
+        let logger = ::Logger::new().output(&::log::Level::Error, ::Output::writer(file));
+        ::log::Log::log(&logger, &::log::Record::builder()
+            .level(::log::Level::Error)
+            .args(format_args!("written to stderr"))
+            .build());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first\n");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn period_works() {
+        let dir = temp_dir("period");
+        let path = dir.join("app.log");
+        let mut file = Rotation::new(&path).period(Period::Hourly).open().unwrap();
+        file.write_all(b"first\n").unwrap();
+        file.period = Some(String::from("1970010100"));
+        file.write_all(b"second\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second\n");
+        assert_eq!(fs::read_to_string(dir.join("app.log.1")).unwrap(), "first\n");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn compress_works() {
+        use flate2::read::GzDecoder;
+        use std::io::Read;
+
+        let dir = temp_dir("compress");
+        let path = dir.join("app.log");
+        let mut file = Rotation::new(&path).max_size(8).compress(true).open().unwrap();
+        file.write_all(b"first\n").unwrap();
+        file.write_all(b"second\n").unwrap();
+        let mut rotated = String::new();
+        GzDecoder::new(File::open(dir.join("app.log.1.gz")).unwrap())
+            .read_to_string(&mut rotated)
+            .unwrap();
+        assert_eq!(rotated, "first\n");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
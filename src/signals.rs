@@ -0,0 +1,88 @@
+//! Changes to the verbosity of an initialized logger with Unix signals.
+
+use std::cmp;
+use std::io;
+use std::thread;
+
+use log;
+use signal_hook::consts::{SIGUSR1, SIGUSR2};
+use signal_hook::iterator::Signals;
+
+use LoggerHandle;
+
+impl LoggerHandle {
+    /// Raises the verbosity by one on `SIGUSR1` and lowers it by one on `SIGUSR2`.
+    ///
+    /// This uses the same scale as the `verbosity` method with the offset from the `base_level`
+    /// method, starting from the current level, and a notice with the new level is printed to
+    /// `stderr` for each signal. The level goes no lower than ERROR and no higher than TRACE, and
+    /// a level set with directives is replaced. The signals are handled on a background thread for
+    /// the rest of the program.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     let handle = loggerv::Logger::new().init().unwrap();
+    ///     handle.listen_for_verbosity_signals().unwrap();
+    ///
+    ///     trace!("This is printed after `kill -USR1` is sent three times");
+    /// }
+    /// ```
+    pub fn listen_for_verbosity_signals(&self) -> io::Result<()> {
+        let mut signals = Signals::new([SIGUSR1, SIGUSR2])?;
+        let handle = self.clone();
+        thread::Builder::new()
+            .name(String::from("loggerv-signals"))
+            .spawn(move || {
+                for signal in signals.forever() {
+                    let level = handle.step_verbosity(signal == SIGUSR1);
+                    eprintln!("loggerv: level changed to {}", level);
+                }
+            })?;
+        Ok(())
+    }
+
+    /// Raises or lowers the verbosity by one and gets the new level.
+    fn step_verbosity(&self, raise: bool) -> log::Level {
+        self.update(|mut logger| {
+            // The scale of verbosity plus offset is 0 for ERROR to 4 for TRACE, which is one less
+            // than the level filters, since those start with OFF.
+            let current = cmp::max(logger.global_level() as u64, 1) - 1;
+            let next = if raise { cmp::min(current + 1, 4) } else { current.saturating_sub(1) };
+            logger.level_override = None;
+            if next >= logger.offset {
+                let offset = logger.offset;
+                logger.verbosity(next - offset)
+            } else {
+                logger.max_level(log::Level::iter().nth(next as usize).unwrap_or(log::Level::Error))
+            }
+        });
+        self.logger.read().unwrap_or_else(|e| e.into_inner()).level
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, RwLock};
+    use super::*;
+    use Logger;
+
+    #[test]
+    fn step_verbosity_works() {
+        let handle = LoggerHandle { logger: Arc::new(RwLock::new(Logger::new().verbosity(1))) };
+        handle.update(|logger| logger);
+        assert_eq!(handle.step_verbosity(true), log::Level::Debug);
+        assert_eq!(handle.step_verbosity(true), log::Level::Trace);
+        assert_eq!(handle.step_verbosity(true), log::Level::Trace);
+        assert_eq!(handle.step_verbosity(false), log::Level::Debug);
+        handle.set_max_level(log::Level::Warn);
+        assert_eq!(handle.step_verbosity(false), log::Level::Error);
+        assert_eq!(handle.step_verbosity(false), log::Level::Error);
+        assert_eq!(handle.step_verbosity(true), log::Level::Warn);
+        assert_eq!(handle.logger.read().unwrap().verbosity, Some(0));
+    }
+}
@@ -0,0 +1,465 @@
+//! An output to a syslog daemon over a local unix socket, UDP, or TCP.
+
+use std::env;
+use std::fmt;
+use std::io::{self, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+#[cfg(unix)]
+use std::os::unix::net::UnixDatagram;
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::{Local, SecondsFormat};
+use log;
+
+/// The default path of the local syslog socket.
+#[cfg(unix)]
+pub const DEFAULT_SYSLOG_PATH: &str = "/dev/log";
+
+/// The format of the messages sent to the syslog daemon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyslogFormat {
+    /// The BSD syslog protocol, such as `<11>Feb  3 15:05:09 app[42]: message`.
+    Rfc3164,
+    /// The syslog protocol, such as `<11>1 2018-02-03T15:05:09.123+01:00 host app 42 - - message`.
+    Rfc5424,
+}
+
+/// The syslog facility, which is the type of program sending the messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Facility {
+    Kern = 0,
+    User = 1,
+    Mail = 2,
+    Daemon = 3,
+    Auth = 4,
+    Syslog = 5,
+    Lpr = 6,
+    News = 7,
+    Uucp = 8,
+    Cron = 9,
+    AuthPriv = 10,
+    Ftp = 11,
+    Local0 = 16,
+    Local1 = 17,
+    Local2 = 18,
+    Local3 = 19,
+    Local4 = 20,
+    Local5 = 21,
+    Local6 = 22,
+    Local7 = 23,
+}
+
+/// Maps a level to a syslog severity.
+///
+/// DEBUG and TRACE are both mapped to the debug severity because syslog has nothing below it.
+pub fn severity(l: log::Level) -> u8 {
+    match l {
+        log::Level::Error => 3,
+        log::Level::Warn => 4,
+        log::Level::Info => 6,
+        log::Level::Debug | log::Level::Trace => 7,
+    }
+}
+
+/// How long to wait for a TCP connection to the syslog daemon.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How long to wait before reconnecting to the syslog daemon over TCP after the first failure,
+/// which is doubled with every failure up to the longest wait.
+const MIN_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+enum Transport {
+    #[cfg(unix)]
+    Unix {
+        path: PathBuf,
+        socket: UnixDatagram,
+    },
+    Udp(UdpSocket),
+    Tcp {
+        addr: SocketAddr,
+        stream: Option<TcpStream>,
+        delay: Duration,
+        reconnect_at: Option<Instant>,
+    },
+}
+
+impl Transport {
+    fn send(&mut self, message: &[u8]) -> io::Result<()> {
+        match *self {
+            #[cfg(unix)]
+            Transport::Unix { ref path, ref socket } => {
+                // The connection is lost when the syslog daemon is restarted, so reconnect once.
+                socket.send(message).map(|_| ()).or_else(|_| {
+                    socket.connect(path)?;
+                    socket.send(message).map(|_| ())
+                })
+            },
+            Transport::Udp(ref socket) => socket.send(message).map(|_| ()),
+            Transport::Tcp { addr, ref mut stream, ref mut delay, ref mut reconnect_at } => {
+                if let Some(ref mut s) = *stream {
+                    if s.write_all(message).is_ok() {
+                        return Ok(());
+                    }
+                }
+                *stream = None;
+                // Reconnecting is put off after a failure, so the logging threads do not all
+                // wait for the connection timeout while the syslog daemon is down.
+                let now = Instant::now();
+                if reconnect_at.is_some_and(|at| now < at) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::NotConnected,
+                        "waiting to reconnect to the syslog daemon",
+                    ));
+                }
+                let result = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)
+                    .and_then(|mut s| s.write_all(message).map(|()| s));
+                match result {
+                    Ok(s) => {
+                        *stream = Some(s);
+                        *delay = MIN_RECONNECT_DELAY;
+                        *reconnect_at = None;
+                        Ok(())
+                    },
+                    Err(e) => {
+                        *reconnect_at = Some(now + *delay);
+                        *delay = std::cmp::min(*delay * 2, MAX_RECONNECT_DELAY);
+                        Err(e)
+                    },
+                }
+            },
+        }
+    }
+}
+
+/// An output to a syslog daemon.
+///
+/// When a message cannot be sent, such as while the syslog daemon is restarting, the log statement
+/// is written to `stderr` instead, and the first failure is reported there.
+///
+/// Clones of the output share the connection. Two outputs are equal if they have the same
+/// configuration and share the connection.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// #[macro_use] extern crate log;
+/// extern crate loggerv;
+///
+/// use log::Level;
+/// use loggerv::{Facility, Output, Syslog};
+///
+/// fn main() {
+///     let syslog = Output::Syslog(Syslog::unix().unwrap().facility(Facility::Daemon));
+///     loggerv::Logger::new()
+///         .output(&Level::Error, syslog.clone())
+///         .output(&Level::Warn, syslog)
+///         .init()
+///         .unwrap();
+///
+///     error!("This is sent to syslog with the error severity");
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Syslog {
+    format: SyslogFormat,
+    facility: Facility,
+    hostname: Option<String>,
+    app_name: String,
+    transport: Arc<Mutex<Transport>>,
+    failed: Arc<AtomicBool>,
+}
+
+impl Syslog {
+    /// Creates an output to the local syslog daemon over the `/dev/log` unix socket.
+    #[cfg(unix)]
+    pub fn unix() -> io::Result<Syslog> {
+        Syslog::unix_path(DEFAULT_SYSLOG_PATH)
+    }
+
+    /// Creates an output to a syslog daemon over a unix datagram socket.
+    ///
+    /// The hostname is left out of the messages, since the local daemon adds it.
+    #[cfg(unix)]
+    pub fn unix_path<P: AsRef<Path>>(path: P) -> io::Result<Syslog> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(&path)?;
+        Ok(Syslog::with_transport(Transport::Unix {
+            path: path.as_ref().to_path_buf(),
+            socket,
+        }, None))
+    }
+
+    /// Creates an output to a remote syslog daemon over UDP.
+    pub fn udp<A: ToSocketAddrs>(addr: A) -> io::Result<Syslog> {
+        let addr = resolve(addr)?;
+        let local: SocketAddr = if addr.is_ipv4() {
+            ([0, 0, 0, 0], 0).into()
+        } else {
+            ([0u16; 8], 0).into()
+        };
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(addr)?;
+        Ok(Syslog::with_transport(Transport::Udp(socket), Some(hostname())))
+    }
+
+    /// Creates an output to a remote syslog daemon over TCP.
+    ///
+    /// RFC 3164 messages are terminated by a newline, while RFC 5424 messages are prefixed with
+    /// their length as described in RFC 6587. The connection is reopened if it is lost, waiting
+    /// longer between attempts while the syslog daemon cannot be reached, up to a minute.
+    pub fn tcp<A: ToSocketAddrs>(addr: A) -> io::Result<Syslog> {
+        let addr = resolve(addr)?;
+        let stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?;
+        Ok(Syslog::with_transport(Transport::Tcp {
+            addr,
+            stream: Some(stream),
+            delay: MIN_RECONNECT_DELAY,
+            reconnect_at: None,
+        }, Some(hostname())))
+    }
+
+    fn with_transport(transport: Transport, hostname: Option<String>) -> Syslog {
+        Syslog {
+            format: SyslogFormat::Rfc3164,
+            facility: Facility::User,
+            hostname,
+            app_name: app_name(),
+            transport: Arc::new(Mutex::new(transport)),
+            failed: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Sets the format of the messages. The default is RFC 3164.
+    pub fn format(mut self, f: SyslogFormat) -> Self {
+        self.format = f;
+        self
+    }
+
+    /// Sets the facility. The default is the user facility.
+    pub fn facility(mut self, f: Facility) -> Self {
+        self.facility = f;
+        self
+    }
+
+    /// Sets the hostname in the messages. The default is the hostname of the machine for UDP and
+    /// TCP.
+    pub fn hostname(mut self, h: impl Into<String>) -> Self {
+        self.hostname = Some(h.into());
+        self
+    }
+
+    /// Sets the application name in the messages. The default is the file name of the current
+    /// executable.
+    pub fn app_name(mut self, a: impl Into<String>) -> Self {
+        self.app_name = a.into();
+        self
+    }
+
+    /// Sends a log statement with a level and text to the syslog daemon.
+    pub(crate) fn send(&self, level: log::Level, text: &str) -> io::Result<()> {
+        let message = self.create_message(level, text);
+        self.transport.lock().unwrap_or_else(|e| e.into_inner()).send(message.as_bytes())
+    }
+
+    /// Checks if a failure to send is the first one, which is the only one that is reported.
+    pub(crate) fn first_failure(&self) -> bool {
+        !self.failed.swap(true, Ordering::Relaxed)
+    }
+
+    fn create_message(&self, level: log::Level, text: &str) -> String {
+        let pri = self.facility as u8 * 8 + severity(level);
+        let now = Local::now();
+        let tcp = matches!(
+            *self.transport.lock().unwrap_or_else(|e| e.into_inner()),
+            Transport::Tcp { .. }
+        );
+        match self.format {
+            SyslogFormat::Rfc3164 => {
+                let hostname = match self.hostname {
+                    Some(ref h) => format!("{} ", h),
+                    None => String::new(),
+                };
+                let mut message = format!(
+                    "<{}>{} {}{}[{}]: {}",
+                    pri,
+                    now.format("%b %e %H:%M:%S"),
+                    hostname,
+                    self.app_name,
+                    process::id(),
+                    text
+                );
+                if tcp {
+                    message.push('\n');
+                }
+                message
+            },
+            SyslogFormat::Rfc5424 => {
+                let message = format!(
+                    "<{}>1 {} {} {} {} - - {}",
+                    pri,
+                    now.to_rfc3339_opts(SecondsFormat::Micros, false),
+                    self.hostname.as_ref().map_or("-", |h| h.as_str()),
+                    self.app_name,
+                    process::id(),
+                    text
+                );
+                if tcp {
+                    format!("{} {}", message.len(), message)
+                } else {
+                    message
+                }
+            },
+        }
+    }
+}
+
+impl fmt::Debug for Syslog {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Syslog")
+            .field("format", &self.format)
+            .field("facility", &self.facility)
+            .field("hostname", &self.hostname)
+            .field("app_name", &self.app_name)
+            .finish()
+    }
+}
+
+impl PartialEq for Syslog {
+    fn eq(&self, other: &Syslog) -> bool {
+        self.format == other.format
+            && self.facility == other.facility
+            && self.hostname == other.hostname
+            && self.app_name == other.app_name
+            && Arc::ptr_eq(&self.transport, &other.transport)
+    }
+}
+
+impl Eq for Syslog {}
+
+fn resolve<A: ToSocketAddrs>(addr: A) -> io::Result<SocketAddr> {
+    addr.to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no address to connect to"))
+}
+
+/// Gets the file name of the current executable, truncated to the 32 characters allowed by RFC
+/// 3164.
+fn app_name() -> String {
+    env::current_exe()
+        .ok()
+        .and_then(|p| p.file_name().map(|n| n.to_string_lossy().chars().take(32).collect()))
+        .unwrap_or_else(|| String::from("-"))
+}
+
+#[cfg(unix)]
+fn hostname() -> String {
+    let mut buf = [0u8; 256];
+    let result = unsafe { ::libc::gethostname(buf.as_mut_ptr() as *mut ::libc::c_char, buf.len()) };
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    if result == 0 && len > 0 {
+        String::from_utf8_lossy(&buf[..len]).into_owned()
+    } else {
+        String::from("localhost")
+    }
+}
+
+#[cfg(not(unix))]
+fn hostname() -> String {
+    env::var("COMPUTERNAME").unwrap_or_else(|_| String::from("localhost"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use super::*;
+
+    #[test]
+    fn severity_works() {
+        assert_eq!(severity(log::Level::Error), 3);
+        assert_eq!(severity(log::Level::Warn), 4);
+        assert_eq!(severity(log::Level::Info), 6);
+        assert_eq!(severity(log::Level::Debug), 7);
+        assert_eq!(severity(log::Level::Trace), 7);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_rfc3164_works() {
+        let path = env::temp_dir().join(format!("loggerv-syslog-{}.sock", process::id()));
+        let _ = fs::remove_file(&path);
+        let server = UnixDatagram::bind(&path).unwrap();
+        let syslog = Syslog::unix_path(&path).unwrap().facility(Facility::Daemon).app_name("app");
+        syslog.send(log::Level::Warn, "mycrate: disk is almost full").unwrap();
+        let mut buf = [0u8; 1024];
+        let len = server.recv(&mut buf).unwrap();
+        let message = String::from_utf8_lossy(&buf[..len]).into_owned();
+        assert!(message.starts_with("<28>"));
+        assert!(message.ends_with(&format!(" app[{}]: mycrate: disk is almost full", process::id())));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn tcp_rfc5424_works() {
+        use std::io::Read;
+        use std::net::TcpListener;
+
+        let server = TcpListener::bind("127.0.0.1:0").unwrap();
+        let syslog = Syslog::tcp(server.local_addr().unwrap())
+            .unwrap()
+            .format(SyslogFormat::Rfc5424)
+            .hostname("host")
+            .app_name("app");
+        syslog.send(log::Level::Error, "failed").unwrap();
+        syslog.send(log::Level::Info, "retried").unwrap();
+        drop(syslog);
+        let mut received = String::new();
+        server.accept().unwrap().0.read_to_string(&mut received).unwrap();
+        let mut rest = received.as_str();
+        for (pri, text) in &[("<11>1 ", " failed"), ("<14>1 ", " retried")] {
+            let (len, frame) = rest.split_at(rest.find(' ').unwrap());
+            let len: usize = len.parse().unwrap();
+            let message = &frame[1..len + 1];
+            assert!(message.starts_with(pri));
+            assert!(message.ends_with(&format!(" host app {} - -{}", process::id(), text)));
+            rest = &frame[len + 1..];
+        }
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn tcp_reconnect_works() {
+        let server = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        let syslog = Syslog::tcp(addr).unwrap();
+        drop(server);
+        // The first write to the closed connection can still succeed, so it is written until the
+        // connection fails.
+        while syslog.send(log::Level::Error, "lost").is_ok() {}
+        let started = Instant::now();
+        let error = syslog.send(log::Level::Error, "lost").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotConnected);
+        assert!(started.elapsed() < CONNECT_TIMEOUT);
+    }
+
+    #[test]
+    fn udp_rfc5424_works() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let syslog = Syslog::udp(server.local_addr().unwrap())
+            .unwrap()
+            .format(SyslogFormat::Rfc5424)
+            .hostname("host")
+            .app_name("app");
+        syslog.send(log::Level::Error, "failed").unwrap();
+        let mut buf = [0u8; 1024];
+        let len = server.recv(&mut buf).unwrap();
+        let message = String::from_utf8_lossy(&buf[..len]).into_owned();
+        assert!(message.starts_with("<11>1 "));
+        assert!(message.ends_with(&format!(" host app {} - - failed", process::id())));
+    }
+}
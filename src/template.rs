@@ -0,0 +1,328 @@
+//! A template language for the layout of a log statement.
+//!
+//! A template is text with fields in curly braces, such as `{level:>5} {module} | {msg}`. A field
+//! is a name optionally followed by a colon and a format. The format is an optional fill character
+//! and alignment (`<`, `>`, or `^`), an optional width, and optional space-separated style words,
+//! such as `{module:<20 dim}` or `{level:bold}`. Use `{{` and `}}` for literal curly braces.
+
+use std::error::Error;
+use std::fmt;
+use std::mem;
+
+use ansi_term::{Colour, Style};
+use log;
+
+use format;
+use {thread_color, Logger, Timestamp, MODULE_PATH_UNKNOWN};
+
+/// An error from parsing a template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    /// A field is opened with `{` but never closed with `}`.
+    UnclosedField(String),
+    /// A `}` is not part of a field and not escaped as `}}`.
+    UnmatchedBrace,
+    /// A field name is not one of the known fields.
+    UnknownField(String),
+    /// A style word in the format of a field is not a known style.
+    UnknownStyle(String),
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TemplateError::UnclosedField(ref t) => write!(f, "unclosed field '{{{}'", t),
+            TemplateError::UnmatchedBrace => write!(f, "unmatched '}}', use '}}}}' for a literal"),
+            TemplateError::UnknownField(ref n) => write!(f, "unknown field '{}'", n),
+            TemplateError::UnknownStyle(ref w) => write!(f, "unknown style '{}'", w),
+        }
+    }
+}
+
+impl Error for TemplateError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Time,
+    Level,
+    Module,
+    File,
+    Line,
+    Thread,
+    Message,
+    KeyValues,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Literal(String),
+    Field {
+        field: Field,
+        fill: char,
+        align: Align,
+        width: usize,
+        style: Option<Style>,
+    },
+}
+
+/// A parsed template.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    /// Parses a template.
+    pub fn parse(t: &str) -> Result<Template, TemplateError> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = t.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                },
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                },
+                '{' => {
+                    let mut text = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => text.push(c),
+                            None => return Err(TemplateError::UnclosedField(text)),
+                        }
+                    }
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(mem::take(&mut literal)));
+                    }
+                    parts.push(parse_field(&text)?);
+                },
+                '}' => return Err(TemplateError::UnmatchedBrace),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        Ok(Template { parts })
+    }
+
+    /// Renders a log statement with the template.
+    pub fn render(&self, logger: &Logger, record: &log::Record, colors: bool) -> String {
+        let mut line = String::new();
+        for part in &self.parts {
+            match *part {
+                Part::Literal(ref l) => line.push_str(l),
+                Part::Field { field, fill, align, width, style } => {
+                    let text = match field {
+                        Field::Time => logger.create_timestamp(match logger.timestamp {
+                            Timestamp::Off => Timestamp::Rfc3339Utc,
+                            t => t,
+                        }),
+                        Field::Level => record.level().to_string(),
+                        Field::Module => {
+                            record.module_path().unwrap_or(MODULE_PATH_UNKNOWN).to_string()
+                        },
+                        Field::File => record.file().unwrap_or(MODULE_PATH_UNKNOWN).to_string(),
+                        Field::Line => record.line().map(|l| l.to_string()).unwrap_or_default(),
+                        Field::Thread => format::thread_name(),
+                        Field::Message => record.args().to_string(),
+                        Field::KeyValues => {
+                            logger.create_key_values(&format::key_values(record), record.level(), false)
+                        },
+                    };
+                    let text = pad(text, fill, align, width);
+                    let style = match style {
+                        Some(s) => Some(s),
+                        None if field == Field::Level => {
+                            Some(logger.select_style(&record.level()))
+                        },
+                        None if field == Field::Thread && logger.thread_colors => {
+                            Some(thread_color(&format::thread_name()).normal())
+                        },
+                        None => None,
+                    };
+                    match style {
+                        Some(s) if colors => line.push_str(&s.paint(text).to_string()),
+                        _ => line.push_str(&text),
+                    }
+                },
+            }
+        }
+        line
+    }
+}
+
+fn parse_field(text: &str) -> Result<Part, TemplateError> {
+    let mut split = text.splitn(2, ':');
+    let name = split.next().unwrap_or("").trim();
+    let format: Vec<char> = split.next().unwrap_or("").chars().collect();
+    let field = match name {
+        "time" => Field::Time,
+        "level" => Field::Level,
+        "module" => Field::Module,
+        "file" => Field::File,
+        "line" => Field::Line,
+        "thread" => Field::Thread,
+        "msg" => Field::Message,
+        "kv" => Field::KeyValues,
+        _ => return Err(TemplateError::UnknownField(name.into())),
+    };
+    let mut i = 0;
+    let mut fill = ' ';
+    let mut align = Align::Left;
+    if format.len() >= 2 && parse_align(format[1]).is_some() {
+        fill = format[0];
+        align = parse_align(format[1]).unwrap_or(align);
+        i = 2;
+    } else if let Some(a) = format.first().cloned().and_then(parse_align) {
+        align = a;
+        i = 1;
+    }
+    let mut width = 0;
+    while let Some(d) = format.get(i).and_then(|c| c.to_digit(10)) {
+        width = width * 10 + d as usize;
+        i += 1;
+    }
+    let words: String = format[i..].iter().collect();
+    let style = if words.trim().is_empty() {
+        None
+    } else {
+        Some(parse_style(&words)?)
+    };
+    Ok(Part::Field { field, fill, align, width, style })
+}
+
+fn parse_align(c: char) -> Option<Align> {
+    match c {
+        '<' => Some(Align::Left),
+        '>' => Some(Align::Right),
+        '^' => Some(Align::Center),
+        _ => None,
+    }
+}
+
+/// Parses space-separated style words, such as `bold red on_white`, into a style.
+fn parse_style(words: &str) -> Result<Style, TemplateError> {
+    let mut style = Style::new();
+    for word in words.split_whitespace() {
+        style = match word {
+            "bold" => style.bold(),
+            "dim" | "dimmed" => style.dimmed(),
+            "italic" => style.italic(),
+            "underline" => style.underline(),
+            "blink" => style.blink(),
+            "reverse" => style.reverse(),
+            "hidden" => style.hidden(),
+            "strikethrough" => style.strikethrough(),
+            _ => match word.strip_prefix("on_") {
+                Some(c) => style.on(parse_colour(c)
+                    .ok_or_else(|| TemplateError::UnknownStyle(word.into()))?),
+                None => style.fg(parse_colour(word)
+                    .ok_or_else(|| TemplateError::UnknownStyle(word.into()))?),
+            },
+        };
+    }
+    Ok(style)
+}
+
+fn parse_colour(name: &str) -> Option<Colour> {
+    match name {
+        "black" => Some(Colour::Black),
+        "red" => Some(Colour::Red),
+        "green" => Some(Colour::Green),
+        "yellow" => Some(Colour::Yellow),
+        "blue" => Some(Colour::Blue),
+        "purple" | "magenta" => Some(Colour::Purple),
+        "cyan" => Some(Colour::Cyan),
+        "white" => Some(Colour::White),
+        _ => None,
+    }
+}
+
+fn pad(text: String, fill: char, align: Align, width: usize) -> String {
+    let len = text.chars().count();
+    if len >= width {
+        return text;
+    }
+    let padding = width - len;
+    let (left, right) = match align {
+        Align::Left => (0, padding),
+        Align::Right => (padding, 0),
+        Align::Center => (padding / 2, padding - padding / 2),
+    };
+    let mut padded = String::with_capacity(text.len() + padding);
+    padded.extend((0..left).map(|_| fill));
+    padded.push_str(&text);
+    padded.extend((0..right).map(|_| fill));
+    padded
+}
+
+#[cfg(test)]
+mod tests {
+    use ansi_term::{Colour, Style};
+    use super::*;
+
+    #[test]
+    fn parse_works() {
+        let template = Template::parse("{{{level:>5 bold red}}} {module:-<8}|{msg}").unwrap();
+        assert_eq!(template.parts, vec![
+            Part::Literal(String::from("{")),
+            Part::Field {
+                field: Field::Level,
+                fill: ' ',
+                align: Align::Right,
+                width: 5,
+                style: Some(Style::new().bold().fg(Colour::Red)),
+            },
+            Part::Literal(String::from("} ")),
+            Part::Field {
+                field: Field::Module,
+                fill: '-',
+                align: Align::Left,
+                width: 8,
+                style: None,
+            },
+            Part::Literal(String::from("|")),
+            Part::Field {
+                field: Field::Message,
+                fill: ' ',
+                align: Align::Left,
+                width: 0,
+                style: None,
+            },
+        ]);
+    }
+
+    #[test]
+    fn parse_errors_work() {
+        assert_eq!(Template::parse("{msg"), Err(TemplateError::UnclosedField(String::from("msg"))));
+        assert_eq!(Template::parse("msg}"), Err(TemplateError::UnmatchedBrace));
+        assert_eq!(Template::parse("{mesg}"), Err(TemplateError::UnknownField(String::from("mesg"))));
+        assert_eq!(Template::parse("{msg:loud}"), Err(TemplateError::UnknownStyle(String::from("loud"))));
+    }
+
+    #[test]
+    fn render_works() {
+        let logger = Logger::new().no_colors();
+        let template = Template::parse("{level:^7}|{module:>8}:{line} {msg}").unwrap();
+        let record = log::Record::builder()
+            .level(log::Level::Warn)
+            .module_path(Some("mycrate"))
+            .line(Some(42))
+            .args(format_args!("hello"))
+            .build();
+        assert_eq!(template.render(&logger, &record, false), " WARN  | mycrate:42 hello");
+    }
+}
@@ -0,0 +1,180 @@
+//! Capturing log statements to assert on them in tests.
+//!
+//! A capture is an output that collects the formatted log statements and the records they were
+//! created from. Since only one logger can be initialized for a program, tests that need their
+//! own configuration can call the `log` method of the `log::Log` trait on a logger directly
+//! instead of initializing it.
+//!
+//! # Example
+//!
+//! ```rust
+//! extern crate log;
+//! extern crate loggerv;
+//!
+//! use log::Log;
+//!
+//! fn main() {
+//!     let capture = loggerv::testing::capture();
+//!     let logger = capture.logger().level(true);
+//!
+//!     logger.log(&log::Record::builder()
+//!         .level(log::Level::Error)
+//!         .module_path(Some("app"))
+//!         .args(format_args!("failed"))
+//!         .build());
+//!
+//!     assert_eq!(capture.lines(), vec!["ERROR [app]: failed"]);
+//!     assert_eq!(capture.records()[0].message, "failed");
+//! }
+//! ```
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use log;
+
+use format;
+use {Logger, Output};
+
+/// A log statement collected by a capture.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedRecord {
+    /// The level of the log statement.
+    pub level: log::Level,
+    /// The target of the log statement.
+    pub target: String,
+    /// The module path of the log statement, if known.
+    pub module_path: Option<String>,
+    /// The source file of the log statement, if known.
+    pub file: Option<String>,
+    /// The line number of the log statement, if known.
+    pub line: Option<u32>,
+    /// The message of the log statement without the tag.
+    pub message: String,
+    /// The key-value pairs of the log statement with the values formatted as text.
+    pub key_values: Vec<(String, String)>,
+    /// The complete log statement as it would have been written to a stream.
+    pub text: String,
+}
+
+impl CapturedRecord {
+    pub(crate) fn new(record: &log::Record, text: &str) -> CapturedRecord {
+        CapturedRecord {
+            level: record.level(),
+            target: record.target().to_string(),
+            module_path: record.module_path().map(String::from),
+            file: record.file().map(String::from),
+            line: record.line(),
+            message: record.args().to_string(),
+            key_values: format::key_values(record).into_iter().map(|kv| (kv.key, kv.value)).collect(),
+            text: text.to_string(),
+        }
+    }
+}
+
+/// An output that collects log statements in memory.
+///
+/// Clones of the capture share the collected log statements. Two captures are equal if they share
+/// the collected log statements.
+#[derive(Clone, Default)]
+pub struct Capture {
+    records: Arc<Mutex<Vec<CapturedRecord>>>,
+}
+
+/// Creates a new, empty capture.
+pub fn capture() -> Capture {
+    Capture::default()
+}
+
+impl Capture {
+    /// Creates an output to the capture.
+    pub fn output(&self) -> Output {
+        Output::Capture(self.clone())
+    }
+
+    /// Creates a logger with the default configuration and the TRACE level that writes all of the
+    /// levels to the capture.
+    pub fn logger(&self) -> Logger {
+        log::Level::iter().fold(
+            Logger::new().max_level(log::Level::Trace),
+            |logger, l| logger.output(&l, self.output()),
+        )
+    }
+
+    /// Gets the complete log statements collected so far.
+    pub fn lines(&self) -> Vec<String> {
+        self.lock().iter().map(|r| r.text.clone()).collect()
+    }
+
+    /// Gets the records of the log statements collected so far.
+    pub fn records(&self) -> Vec<CapturedRecord> {
+        self.lock().clone()
+    }
+
+    /// Removes all of the collected log statements.
+    pub fn clear(&self) {
+        self.lock().clear()
+    }
+
+    pub(crate) fn push(&self, record: &log::Record, text: &str) {
+        self.lock().push(CapturedRecord::new(record, text))
+    }
+
+    fn lock(&self) -> ::std::sync::MutexGuard<'_, Vec<CapturedRecord>> {
+        self.records.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl fmt::Debug for Capture {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Capture({} records)", self.lock().len())
+    }
+}
+
+impl PartialEq for Capture {
+    fn eq(&self, other: &Capture) -> bool {
+        Arc::ptr_eq(&self.records, &other.records)
+    }
+}
+
+impl Eq for Capture {}
+
+#[cfg(test)]
+mod tests {
+    use log::Log;
+    use super::*;
+
+    #[test]
+    fn capture_works() {
+        let capture = capture();
+        let logger = capture.logger().line_numbers(true);
+        logger.log(&log::Record::builder()
+            .level(log::Level::Debug)
+            .target("app::db")
+            .module_path(Some("app::db"))
+            .file(Some("src/db.rs"))
+            .line(Some(12))
+            .args(format_args!("connected to {}", "db"))
+            .build());
+        assert_eq!(capture.lines(), vec!["app::db (line 12): connected to db"]);
+        assert_eq!(capture.records(), vec![CapturedRecord {
+            level: log::Level::Debug,
+            target: String::from("app::db"),
+            module_path: Some(String::from("app::db")),
+            file: Some(String::from("src/db.rs")),
+            line: Some(12),
+            message: String::from("connected to db"),
+            key_values: Vec::new(),
+            text: String::from("app::db (line 12): connected to db"),
+        }]);
+        capture.clear();
+        assert!(capture.records().is_empty());
+    }
+
+    #[test]
+    fn capture_equality_works() {
+        let capture = capture();
+        assert_eq!(capture.output(), capture.clone().output());
+        assert_ne!(capture.output(), super::capture().output());
+    }
+}
@@ -0,0 +1,172 @@
+//! Measuring and fitting text as it is shown on a terminal.
+
+use unicode_width::UnicodeWidthChar;
+
+/// Removes the ANSI escape sequences, such as the colors, from text.
+pub fn strip_ansi(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' && chars.peek() == Some(&'[') {
+            chars.next();
+            // A control sequence ends with a byte from `@` to `~`.
+            for c in chars.by_ref() {
+                if ('@'..='~').contains(&c) {
+                    break;
+                }
+            }
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+/// Gets the number of columns that text takes on a terminal, ignoring ANSI escape sequences.
+pub fn width(text: &str) -> usize {
+    segments(text).map(|(_, w)| w).sum()
+}
+
+/// The narrowest width left for the text of a wrapped line before the indentation is shortened.
+const MIN_WRAPPED_WIDTH: usize = 20;
+
+/// The shortest indentation of wrapped lines, so they are never mistaken for new lines.
+const MIN_INDENT: usize = 2;
+
+/// Wraps the lines of text that are wider than a number of columns, between words where possible.
+/// The wrapped lines are indented by a number of columns, which is shortened down to two columns
+/// if it leaves too little room.
+pub fn wrap(text: &str, columns: usize, indent: usize) -> String {
+    let shortest = indent.min(MIN_INDENT);
+    let indent = indent.min(columns.saturating_sub(MIN_WRAPPED_WIDTH).max(shortest));
+    let mut wrapped = String::with_capacity(text.len());
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            wrapped.push('\n');
+        }
+        wrap_line(&mut wrapped, line, columns, indent);
+    }
+    wrapped
+}
+
+fn wrap_line(wrapped: &mut String, line: &str, columns: usize, indent: usize) {
+    let mut used = 0;
+    for (i, word) in line.split(' ').enumerate() {
+        let word_width = width(word);
+        if i > 0 {
+            if used + 1 + word_width <= columns {
+                wrapped.push(' ');
+                used += 1;
+            } else {
+                new_line(wrapped, indent);
+                used = indent;
+            }
+        }
+        if used + word_width <= columns {
+            wrapped.push_str(word);
+            used += word_width;
+            continue;
+        }
+        // The word does not fit on a line of its own, so it is wrapped where it runs out of room.
+        for (segment, w) in segments(word) {
+            if w > 0 && used + w > columns && used > indent {
+                new_line(wrapped, indent);
+                used = indent;
+            }
+            wrapped.push_str(segment);
+            used += w;
+        }
+    }
+}
+
+fn new_line(wrapped: &mut String, indent: usize) {
+    wrapped.push('\n');
+    wrapped.extend(std::iter::repeat(' ').take(indent));
+}
+
+/// Cuts off the lines of text that are wider than a number of columns, with an ellipsis as the
+/// last character. The escape sequences after the cut are kept, so colors are still reset.
+pub fn truncate(text: &str, columns: usize) -> String {
+    text.split('\n')
+        .map(|line| {
+            if width(line) <= columns {
+                return line.to_string();
+            }
+            let mut truncated = String::with_capacity(line.len());
+            let mut used = 0;
+            let mut cut = false;
+            for (segment, w) in segments(line) {
+                if segment.starts_with('\u{1b}') {
+                    truncated.push_str(segment);
+                } else if !cut && used + w < columns {
+                    truncated.push_str(segment);
+                    used += w;
+                } else if !cut {
+                    truncated.push('…');
+                    cut = true;
+                }
+            }
+            truncated
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Splits text into ANSI escape sequences and characters, along with the number of columns that
+/// each takes on a terminal.
+fn segments(text: &str) -> impl Iterator<Item = (&str, usize)> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        let c = rest.chars().next()?;
+        let len = match rest.strip_prefix("\u{1b}[") {
+            // A control sequence ends with a byte from `@` to `~`.
+            Some(sequence) => {
+                sequence.find(|c| ('@'..='~').contains(&c)).map_or(rest.len(), |i| i + 3)
+            },
+            None => c.len_utf8(),
+        };
+        let (segment, next) = rest.split_at(len);
+        rest = next;
+        let w = if len > c.len_utf8() { 0 } else { c.width().unwrap_or(0) };
+        Some((segment, w))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use ansi_term::Colour;
+    use super::*;
+
+    #[test]
+    fn strip_ansi_works() {
+        let painted = Colour::Red.bold().paint("ERROR").to_string();
+        assert_eq!(strip_ansi(&format!("{} [app]", painted)), "ERROR [app]");
+        assert_eq!(width(&painted), 5);
+        assert_eq!(width("日本"), 4);
+    }
+
+    #[test]
+    fn wrap_works() {
+        assert_eq!(wrap("short", 10, 2), "short");
+        assert_eq!(wrap("one two three four", 10, 0), "one two\nthree four");
+        assert_eq!(wrap("abcdefghijkl", 5, 0), "abcde\nfghij\nkl");
+        assert_eq!(wrap("日本語のテキスト", 6, 0), "日本語\nのテキ\nスト");
+        assert_eq!(
+            wrap("tag: one two three four five six seven", 25, 5),
+            "tag: one two three four\n     five six seven"
+        );
+        assert_eq!(wrap("tag: one two three", 22, 5), "tag: one two three");
+        assert_eq!(wrap("tag: one two three four", 22, 5), "tag: one two three\n  four");
+        assert_eq!(wrap("tag: one two", 6, 5), "tag:\n  one\n  two");
+        let painted = Colour::Red.paint("one two").to_string();
+        assert_eq!(strip_ansi(&wrap(&painted, 4, 0)), "one\ntwo");
+    }
+
+    #[test]
+    fn truncate_works() {
+        assert_eq!(truncate("short\nand longer", 6), "short\nand l…");
+        assert_eq!(truncate("日本語", 4), "日…");
+        let painted = Colour::Red.paint("a long line").to_string();
+        assert_eq!(truncate(&painted, 5), Colour::Red.paint("a lo…").to_string());
+    }
+}